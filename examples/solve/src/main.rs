@@ -22,9 +22,12 @@ struct CliArgs {
     /// api result url
     #[structopt(long = "api-result-url", default_value = API_RESULT_URL)]
     api_result_url: String,
-    /// results poll interval timeout (in milliseconds)
-    #[structopt(long = "poll-timeout-ms", default_value = "5000")]
-    poll_timeout_ms: u64,
+    /// delay before the first results poll (in milliseconds)
+    #[structopt(long = "initial-delay-ms")]
+    initial_delay_ms: Option<u64>,
+    /// results poll interval (in milliseconds)
+    #[structopt(long = "poll-interval-ms")]
+    poll_interval_ms: Option<u64>,
     /// force base64 mode
     #[structopt(long = "force-base64")]
     force_base64: bool,
@@ -60,7 +63,9 @@ async fn main() -> Result<(), Error> {
         two_captcha::Params {
             api_request_url: cli_args.api_request_url,
             api_result_url: cli_args.api_result_url,
-            poll_timeout_ms: cli_args.poll_timeout_ms,
+            maybe_initial_delay_ms: cli_args.initial_delay_ms,
+            maybe_poll_interval_ms: cli_args.poll_interval_ms,
+            ..Default::default()
         },
     );
 
@@ -0,0 +1,197 @@
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+    Deserialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    proxy::{
+        Proxy,
+        TaskProxy,
+    },
+};
+
+pub struct Captcha {
+    sitekey: String,
+    iv: String,
+    context: String,
+    pageurl: String,
+    maybe_challenge_script: Option<String>,
+    maybe_captcha_script: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_sitekey: Option<String>,
+    maybe_iv: Option<String>,
+    maybe_context: Option<String>,
+    maybe_pageurl: Option<String>,
+    maybe_challenge_script: Option<String>,
+    maybe_captcha_script: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Debug)]
+pub struct Answer {
+    pub captcha_voucher: String,
+    pub existing_token: String,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    SitekeyIsNotProvided,
+    IvIsNotProvided,
+    ContextIsNotProvided,
+    PageurlIsNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_sitekey<T>(mut self, sitekey: T) -> Self where T: AsRef<str> {
+        self.maybe_sitekey = Some(sitekey.as_ref().to_string());
+        self
+    }
+
+    pub fn set_iv<T>(mut self, iv: T) -> Self where T: AsRef<str> {
+        self.maybe_iv = Some(iv.as_ref().to_string());
+        self
+    }
+
+    pub fn set_context<T>(mut self, context: T) -> Self where T: AsRef<str> {
+        self.maybe_context = Some(context.as_ref().to_string());
+        self
+    }
+
+    pub fn set_pageurl<T>(mut self, pageurl: T) -> Self where T: AsRef<str> {
+        self.maybe_pageurl = Some(pageurl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_challenge_script<T>(mut self, challenge_script: T) -> Self where T: AsRef<str> {
+        self.maybe_challenge_script = Some(challenge_script.as_ref().to_string());
+        self
+    }
+
+    pub fn set_captcha_script<T>(mut self, captcha_script: T) -> Self where T: AsRef<str> {
+        self.maybe_captcha_script = Some(captcha_script.as_ref().to_string());
+        self
+    }
+
+    pub fn set_proxy(mut self, proxy: Proxy) -> Self {
+        self.maybe_proxy = Some(proxy);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let sitekey = self.maybe_sitekey
+            .filter(|sitekey| !sitekey.is_empty())
+            .ok_or(BuilderError::SitekeyIsNotProvided)?;
+        let iv = self.maybe_iv
+            .filter(|iv| !iv.is_empty())
+            .ok_or(BuilderError::IvIsNotProvided)?;
+        let context = self.maybe_context
+            .filter(|context| !context.is_empty())
+            .ok_or(BuilderError::ContextIsNotProvided)?;
+        let pageurl = self.maybe_pageurl
+            .filter(|pageurl| !pageurl.is_empty())
+            .ok_or(BuilderError::PageurlIsNotProvided)?;
+        Ok(Captcha {
+            sitekey,
+            iv,
+            context,
+            pageurl,
+            maybe_challenge_script: self.maybe_challenge_script,
+            maybe_captcha_script: self.maybe_captcha_script,
+            maybe_proxy: self.maybe_proxy,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    #[serde(rename = "websiteURL")]
+    website_url: String,
+    website_key: String,
+    iv: String,
+    context: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    challenge_script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    captcha_script: Option<String>,
+    #[serde(flatten)]
+    maybe_proxy: Option<TaskProxy>,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = Answer;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "amazon_waf";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!("building amazon_waf request for sitekey = {} on {}", self.sitekey, self.pageurl);
+
+        let mut form = vec![
+            ("method", "amazon_waf".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("sitekey", self.sitekey.clone()),
+            ("iv", self.iv.clone()),
+            ("context", self.context.clone()),
+            ("pageurl", self.pageurl.clone()),
+        ];
+        if let Some(challenge_script) = &self.maybe_challenge_script {
+            form.push(("challenge_script", challenge_script.clone()));
+        }
+        if let Some(captcha_script) = &self.maybe_captcha_script {
+            form.push(("captcha_script", captcha_script.clone()));
+        }
+        if let Some(proxy) = &self.maybe_proxy {
+            form.push(("proxy", proxy.proxy_param()));
+            form.push(("proxytype", proxy.proxy_type().as_str().to_string()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: if self.maybe_proxy.is_some() { "AmazonTask" } else { "AmazonTaskProxyless" },
+            website_url: self.pageurl.clone(),
+            website_key: self.sitekey.clone(),
+            iv: self.iv.clone(),
+            context: self.context.clone(),
+            challenge_script: self.maybe_challenge_script.clone(),
+            captcha_script: self.maybe_captcha_script.clone(),
+            maybe_proxy: self.maybe_proxy.as_ref().map(Proxy::task_proxy),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::from_json(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::from_solution(solution)
+    }
+}
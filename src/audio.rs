@@ -0,0 +1,219 @@
+use std::{
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+    },
+};
+
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    IMAGE_INITIAL_DELAY_MS,
+    IMAGE_POLL_INTERVAL_MS,
+};
+
+pub const MAX_AUDIO_SIZE: usize = 1024 * 1024;
+pub const SUPPORTED_LANGS: &[&str] = &["en", "fr", "de", "el", "pt", "ru"];
+
+pub struct Captcha {
+    audio_data: AudioData,
+    maybe_lang: Option<String>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_audio_data: Option<AudioData>,
+    maybe_lang: Option<String>,
+}
+
+enum AudioData {
+    UploadFile(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Debug)]
+pub enum AudioError {
+    Empty,
+    TooBig { size: usize, },
+    UnsupportedFormat,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    InvalidBase64 { source: String, error: base64::DecodeError, },
+    InvalidAudio(AudioError),
+    AudioIsNotProvided,
+    UnsupportedLang { lang: String, },
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_upload_file<P>(mut self, path: P) -> Self where P: AsRef<Path> {
+        self.maybe_audio_data = Some(AudioData::UploadFile(path.as_ref().to_owned()));
+        self
+    }
+
+    pub fn set_audio_data<T>(mut self, audio_data: T) -> Result<Self, BuilderError> where T: AsRef<[u8]> {
+        validate_audio(audio_data.as_ref())
+            .map_err(BuilderError::InvalidAudio)?;
+        self.maybe_audio_data = Some(AudioData::Bytes(audio_data.as_ref().to_vec()));
+        Ok(self)
+    }
+
+    pub fn set_audio_data_base64<T>(mut self, base64_str: T) -> Result<Self, BuilderError> where T: AsRef<str> {
+        let audio_data = base64::decode(base64_str.as_ref())
+            .map_err(|error| {
+                BuilderError::InvalidBase64 {
+                    source: base64_str.as_ref().to_string(),
+                    error,
+                }
+            })?;
+        validate_audio(&audio_data)
+            .map_err(BuilderError::InvalidAudio)?;
+        self.maybe_audio_data = Some(AudioData::Bytes(audio_data));
+        Ok(self)
+    }
+
+    pub fn set_lang<T>(mut self, lang: T) -> Self where T: AsRef<str> {
+        self.maybe_lang = Some(lang.as_ref().to_string());
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let audio_data = self.maybe_audio_data
+            .ok_or(BuilderError::AudioIsNotProvided)?;
+        if let Some(lang) = &self.maybe_lang {
+            if !SUPPORTED_LANGS.contains(&lang.as_str()) {
+                return Err(BuilderError::UnsupportedLang { lang: lang.clone(), });
+            }
+        }
+        Ok(Captcha {
+            audio_data,
+            maybe_lang: self.maybe_lang,
+        })
+    }
+}
+
+fn validate_audio(audio_data: &[u8]) -> Result<(), AudioError> {
+    if audio_data.is_empty() {
+        return Err(AudioError::Empty);
+    }
+    if audio_data.len() > MAX_AUDIO_SIZE {
+        return Err(AudioError::TooBig { size: audio_data.len(), });
+    }
+    let is_id3_tagged = audio_data.starts_with(b"ID3");
+    let is_mpeg_frame = audio_data.len() >= 2 && audio_data[0] == 0xFF && audio_data[1] & 0xE0 == 0xE0;
+    if !is_id3_tagged && !is_mpeg_frame {
+        return Err(AudioError::UnsupportedFormat);
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {
+    AudioFileRead { filename: PathBuf, error: std::io::Error, },
+    InvalidAudioFile { filename: PathBuf, error: AudioError, },
+}
+
+impl AudioData {
+    async fn to_base64(&self) -> Result<String, PrepareRequestError> {
+        match self {
+            AudioData::UploadFile(path_buf) => {
+                let audio_data = tokio::fs::read(path_buf).await
+                    .map_err(|error| {
+                        PrepareRequestError::AudioFileRead {
+                            filename: path_buf.clone(),
+                            error,
+                        }
+                    })?;
+                validate_audio(&audio_data)
+                    .map_err(|error| {
+                        PrepareRequestError::InvalidAudioFile {
+                            filename: path_buf.clone(),
+                            error,
+                        }
+                    })?;
+                Ok(base64::encode(audio_data))
+            },
+            AudioData::Bytes(audio_data) =>
+                Ok(base64::encode(audio_data)),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lang: Option<String>,
+}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "audio";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        let base64_string = self.audio_data.to_base64().await?;
+
+        log::debug!("building audio request with audio base64.len = {}", base64_string.len());
+
+        let mut form = vec![
+            ("method", "audio".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("body", base64_string),
+        ];
+        if let Some(lang) = &self.maybe_lang {
+            form.push(("lang", lang.clone()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: "AudioTask",
+            body: self.audio_data.to_base64().await?,
+            lang: self.maybe_lang.clone(),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "token")
+    }
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(IMAGE_INITIAL_DELAY_MS)
+    }
+
+    fn recommended_poll_interval() -> Duration {
+        Duration::from_millis(IMAGE_POLL_INTERVAL_MS)
+    }
+}
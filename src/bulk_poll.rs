@@ -0,0 +1,327 @@
+use std::{
+    convert::Infallible,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+    },
+    collections::HashMap,
+};
+
+use reqwest::{
+    Client,
+    StatusCode,
+};
+
+use tokio::{
+    sync::{
+        oneshot,
+        Notify,
+    },
+    time::{
+        sleep_until,
+        Instant,
+    },
+};
+
+use crate::{
+    ApiError,
+    CaptchaId,
+    ApiResponse,
+    PollResponseError,
+    retry::RetryPolicy,
+};
+
+pub const BULK_POLL_MAX_IDS: usize = 100;
+
+pub(crate) struct BulkPoller {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    client: Client,
+    key: String,
+    api_result_url: String,
+    retry_policy: RetryPolicy,
+    state: Mutex<State>,
+    registered: Notify,
+}
+
+#[derive(Default)]
+struct State {
+    pending: HashMap<CaptchaId, Waiting>,
+    is_running: bool,
+}
+
+struct Waiting {
+    poll_interval: Duration,
+    next_poll_at: Instant,
+    senders: Vec<oneshot::Sender<Option<String>>>,
+}
+
+impl BulkPoller {
+    pub(crate) fn new(client: Client, key: String, api_result_url: String, retry_policy: RetryPolicy) -> BulkPoller {
+        BulkPoller {
+            inner: Arc::new(Inner {
+                client,
+                key,
+                api_result_url,
+                retry_policy,
+                state: Mutex::new(State::default()),
+                registered: Notify::new(),
+            }),
+        }
+    }
+
+    pub(crate) async fn wait(&self, captcha_id: &CaptchaId, poll_interval: Duration) -> Option<String> {
+        let (sender, receiver) = oneshot::channel();
+        let is_running = {
+            let mut state = self.inner.state.lock().unwrap();
+            let waiting = state.pending.entry(captcha_id.clone())
+                .or_insert_with(|| Waiting { poll_interval, next_poll_at: Instant::now() + poll_interval, senders: Vec::new(), });
+            waiting.poll_interval = waiting.poll_interval.min(poll_interval);
+            waiting.senders.push(sender);
+            std::mem::replace(&mut state.is_running, true)
+        };
+        if is_running {
+            self.inner.registered.notify_one();
+        } else {
+            tokio::spawn(run(self.inner.clone()));
+        }
+        receiver.await.ok().flatten()
+    }
+}
+
+async fn run(inner: Arc<Inner>) {
+    let mut failures = 0;
+    let mut backoff_until = Instant::now();
+    loop {
+        let next_poll_at = {
+            let mut state = inner.state.lock().unwrap();
+            state.pending.retain(|_, waiting| {
+                waiting.senders.retain(|sender| !sender.is_closed());
+                !waiting.senders.is_empty()
+            });
+            match state.pending.values().map(|waiting| waiting.next_poll_at).min() {
+                Some(next_poll_at) =>
+                    next_poll_at.max(backoff_until),
+                None => {
+                    state.is_running = false;
+                    return;
+                },
+            }
+        };
+        tokio::select! {
+            () = sleep_until(next_poll_at) =>
+                (),
+            // a newly registered captcha may be due sooner than the one we are sleeping for
+            () = inner.registered.notified() =>
+                continue,
+        }
+
+        let now = Instant::now();
+        let captcha_ids: Vec<CaptchaId> = {
+            let mut state = inner.state.lock().unwrap();
+            let mut captcha_ids = Vec::new();
+            for (captcha_id, waiting) in state.pending.iter_mut() {
+                if waiting.next_poll_at <= now {
+                    waiting.next_poll_at = now + waiting.poll_interval;
+                    captcha_ids.push(captcha_id.clone());
+                }
+            }
+            captcha_ids
+        };
+
+        let mut is_failed = false;
+        for chunk in captcha_ids.chunks(BULK_POLL_MAX_IDS) {
+            match fetch(&inner, chunk).await {
+                Ok(codes) => {
+                    let mut state = inner.state.lock().unwrap();
+                    for (captcha_id, code) in chunk.iter().zip(codes) {
+                        if code == "CAPCHA_NOT_READY" {
+                            continue;
+                        }
+                        for sender in state.pending.remove(captcha_id).into_iter().flat_map(|waiting| waiting.senders) {
+                            let _ = sender.send(Some(code.clone()));
+                        }
+                    }
+                },
+                Err(error) => {
+                    let is_transient = failures + 1 < inner.retry_policy.max_attempts && error.error_class()
+                        .is_some_and(|error_class| inner.retry_policy.retry_on.contains(&error_class));
+                    if is_transient {
+                        // keep the waiters registered, the chunk is retried on the next round
+                        log::warn!("bulk poll for {} captcha ids failed: {:?}, retrying", chunk.len(), error);
+                        is_failed = true;
+                    } else {
+                        // let the waiters fall back to polling one by one so they get the actual error
+                        log::warn!("bulk poll for {} captcha ids failed: {:?}", chunk.len(), error);
+                        let mut state = inner.state.lock().unwrap();
+                        for captcha_id in chunk {
+                            for sender in state.pending.remove(captcha_id).into_iter().flat_map(|waiting| waiting.senders) {
+                                let _ = sender.send(None);
+                            }
+                        }
+                    }
+                },
+            }
+        }
+        if is_failed {
+            failures += 1;
+            backoff_until = now + inner.retry_policy.delay(failures);
+        } else {
+            failures = 0;
+        }
+    }
+}
+
+async fn fetch(inner: &Inner, captcha_ids: &[CaptchaId]) -> Result<Vec<String>, ApiError<Infallible>> {
+    let ids = captcha_ids.iter()
+        .map(CaptchaId::as_str)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    log::debug!("making bulk poll request with captcha ids = {} to {}", ids, inner.api_result_url);
+
+    let get_parameters = [
+        ("key", &*inner.key),
+        ("action", "get"),
+        ("ids", &ids),
+    ];
+    let response = inner.client.get(&inner.api_result_url)
+        .query(&get_parameters)
+        .send()
+        .await
+        .map_err(ApiError::SendPollRequest)?;
+    let status_code = response.status();
+    if status_code != StatusCode::OK {
+        return Err(ApiError::SendPollRequestBadStatusCode { status_code, });
+    }
+    let api_response_string = response.text().await
+        .map_err(ApiError::ReadPollResponse)?;
+
+    log::debug!("request finished, server responded: {}", api_response_string);
+
+    let codes = if api_response_string.starts_with('{') {
+        ApiResponse::parse(&api_response_string)
+            .map_err(ApiError::DecodePollResponse)?
+            .request
+    } else {
+        api_response_string
+    };
+    let codes: Vec<String> = codes.split('|')
+        .map(|code| code.trim().to_string())
+        .collect();
+    if codes.len() == captcha_ids.len() {
+        return Ok(codes);
+    }
+
+    // a single code for several ids is an error for the whole request, not an answer for each of them
+    let code = codes.join("|");
+    match ApiResponse::from_code(code.clone()).extract_poll_result() {
+        Err(error) =>
+            Err(ApiError::PollResponse(error)),
+        Ok(..) =>
+            Err(ApiError::PollResponse(PollResponseError::UnexpectedApiResponse(ApiResponse::from_code(code)))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    };
+
+    use super::*;
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    fn poller(base_url: &str) -> BulkPoller {
+        let retry_policy = RetryPolicy {
+            initial_delay_ms: 10,
+            max_delay_ms: 20,
+            is_jitter: false,
+            ..RetryPolicy::default()
+        };
+        BulkPoller::new(Client::new(), "key".to_string(), format!("{}/res.php", base_url), retry_policy)
+    }
+
+    #[tokio::test]
+    async fn single_transient_error_keeps_waiters_pending() {
+        let round = Arc::new(AtomicUsize::new(0));
+        let responder_round = round.clone();
+        let (base_url, requests) = crate::tests::stand_in(move |request| {
+            let is_reversed = request.contains("ids=2%2C1");
+            match (responder_round.fetch_add(1, Ordering::SeqCst), is_reversed) {
+                (0, _) => "ERROR: 1001".to_string(),
+                (_, false) => "answer1|answer2".to_string(),
+                (_, true) => "answer2|answer1".to_string(),
+            }
+        }).await;
+        let bulk_poller = poller(&base_url);
+        let (first_id, second_id) = (CaptchaId::from("1".to_string()), CaptchaId::from("2".to_string()));
+
+        let (first, second) = tokio::join!(bulk_poller.wait(&first_id, POLL_INTERVAL), bulk_poller.wait(&second_id, POLL_INTERVAL));
+        assert_eq!(first.as_deref(), Some("answer1"));
+        assert_eq!(second.as_deref(), Some("answer2"));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn single_permanent_error_releases_waiters() {
+        let (base_url, _) = crate::tests::stand_in(|_| "ERROR_WRONG_USER_KEY".to_string()).await;
+        let bulk_poller = poller(&base_url);
+        let (first_id, second_id) = (CaptchaId::from("1".to_string()), CaptchaId::from("2".to_string()));
+
+        let (first, second) = tokio::join!(bulk_poller.wait(&first_id, POLL_INTERVAL), bulk_poller.wait(&second_id, POLL_INTERVAL));
+        assert_eq!(first, None);
+        assert_eq!(second, None);
+    }
+
+    #[tokio::test]
+    async fn not_ready_ids_stay_pending() {
+        let round = Arc::new(AtomicUsize::new(0));
+        let responder_round = round.clone();
+        let (base_url, _) = crate::tests::stand_in(move |_| match responder_round.fetch_add(1, Ordering::SeqCst) {
+            0 => "CAPCHA_NOT_READY".to_string(),
+            _ => "answer".to_string(),
+        }).await;
+        let bulk_poller = poller(&base_url);
+
+        assert_eq!(bulk_poller.wait(&CaptchaId::from("1".to_string()), POLL_INTERVAL).await.as_deref(), Some("answer"));
+        assert_eq!(round.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn ids_are_polled_at_their_own_interval() {
+        let fast_polls = Arc::new(AtomicUsize::new(0));
+        let responder_fast_polls = fast_polls.clone();
+        let (base_url, requests) = crate::tests::stand_in(move |request| {
+            let ids = request.split("ids=").nth(1).unwrap().split(' ').next().unwrap();
+            ids.split("%2C")
+                .map(|captcha_id| match captcha_id {
+                    "1" if responder_fast_polls.fetch_add(1, Ordering::SeqCst) < 3 => "CAPCHA_NOT_READY",
+                    "1" => "fast",
+                    _ => "slow",
+                })
+                .collect::<Vec<_>>()
+                .join("|")
+        }).await;
+        let bulk_poller = poller(&base_url);
+        let (fast_id, slow_id) = (CaptchaId::from("1".to_string()), CaptchaId::from("2".to_string()));
+
+        let (fast, slow) = tokio::join!(
+            bulk_poller.wait(&fast_id, Duration::from_millis(20)),
+            bulk_poller.wait(&slow_id, Duration::from_millis(300)),
+        );
+        assert_eq!(fast.as_deref(), Some("fast"));
+        assert_eq!(slow.as_deref(), Some("slow"));
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 5, "{:?}", requests);
+        assert!(requests[.. 4].iter().all(|request| request.contains("ids=1 ")), "{:?}", requests);
+        assert!(requests[4].contains("ids=2 "), "{:?}", requests);
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    time::{
+        Duration,
+    },
+};
+
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    IMAGE_INITIAL_DELAY_MS,
+    IMAGE_POLL_INTERVAL_MS,
+    normal::{
+        CaptchaData,
+        ImageSource,
+        impl_image_setters,
+    },
+    coordinates::{
+        self,
+        Point,
+    },
+};
+
+pub use crate::normal::PrepareRequestError;
+
+pub struct Captcha {
+    captcha_data: CaptchaData,
+    maybe_text_instructions: Option<String>,
+    maybe_image_instructions: Option<CaptchaData>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    image: ImageSource,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Polygon {
+    pub points: Vec<Point>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    InvalidBase64 { source: String, error: base64::DecodeError, },
+    CaptchaImageIsNotProvided,
+    InstructionsAreNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let ImageSource { maybe_captcha_data, maybe_text_instructions, maybe_image_instructions, } = self.image;
+        let captcha_data = maybe_captcha_data
+            .ok_or(BuilderError::CaptchaImageIsNotProvided)?;
+        if maybe_text_instructions.is_none() && maybe_image_instructions.is_none() {
+            return Err(BuilderError::InstructionsAreNotProvided);
+        }
+        Ok(Captcha {
+            captcha_data,
+            maybe_text_instructions,
+            maybe_image_instructions,
+        })
+    }
+}
+
+impl_image_setters!(CaptchaBuilder);
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    img_instructions: Option<String>,
+}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = Vec<Polygon>;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "canvas";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        let mut params = vec![
+            ("canvas", "1".to_string()),
+        ];
+        if let Some(text_instructions) = &self.maybe_text_instructions {
+            params.push(("textinstructions", text_instructions.clone()));
+        }
+        let mut attachments = vec![];
+        if let Some(image_instructions) = &self.maybe_image_instructions {
+            attachments.push(("imginstructions", image_instructions));
+        }
+        self.captcha_data.prepare_request(None, params, attachments, api_token, request_builder).await
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: "DrawAroundTask",
+            body: self.captcha_data.to_base64().await?,
+            comment: self.maybe_text_instructions.clone(),
+            img_instructions: CaptchaData::maybe_to_base64(self.maybe_image_instructions.as_ref()).await?,
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        match parse_polygons(&answer) {
+            Some(polygons) =>
+                Ok(polygons),
+            None =>
+                Err(DecodeAnswerError::InvalidFormat { source: answer, }),
+        }
+    }
+
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        let maybe_polygons = solution.get("canvas")
+            .and_then(|polygons| parse_polygons(&polygons.to_string()));
+        match maybe_polygons {
+            Some(polygons) =>
+                Ok(polygons),
+            None =>
+                Err(DecodeAnswerError::InvalidFormat { source: solution.to_string(), }),
+        }
+    }
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(IMAGE_INITIAL_DELAY_MS)
+    }
+
+    fn recommended_poll_interval() -> Duration {
+        Duration::from_millis(IMAGE_POLL_INTERVAL_MS)
+    }
+}
+
+fn parse_polygons(answer: &str) -> Option<Vec<Polygon>> {
+    let polygons = answer.strip_prefix("canvas:")
+        .unwrap_or(answer)
+        .trim();
+    match serde_json::from_str::<Vec<serde_json::Value>>(polygons) {
+        Ok(values) if values.iter().all(serde_json::Value::is_array) =>
+            values.iter()
+                .map(|value| coordinates::parse_points(&value.to_string()))
+                .map(|maybe_points| maybe_points.map(|points| Polygon { points, }))
+                .collect(),
+        Ok(..) | Err(..) => {
+            let points = coordinates::parse_points(polygons)?;
+            Some(vec![Polygon { points, }])
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon(points: &[(u32, u32)]) -> Polygon {
+        Polygon { points: points.iter().map(|&(x, y)| Point { x, y, }).collect(), }
+    }
+
+    #[test]
+    fn parse_answer_text_form() {
+        assert_eq!(
+            Captcha::parse_answer("canvas:x=10,y=20;x=30,y=40;x=50,y=60".to_string()).unwrap(),
+            vec![polygon(&[(10, 20), (30, 40), (50, 60)])],
+        );
+    }
+
+    #[test]
+    fn parse_answer_json_points() {
+        assert_eq!(
+            Captcha::parse_answer(r#"canvas:[{"x":10,"y":20},{"x":30,"y":40}]"#.to_string()).unwrap(),
+            vec![polygon(&[(10, 20), (30, 40)])],
+        );
+    }
+
+    #[test]
+    fn parse_answer_json_polygons() {
+        assert_eq!(
+            Captcha::parse_answer(r#"[[{"x":1,"y":2},{"x":3,"y":4}],[{"x":5,"y":6}]]"#.to_string()).unwrap(),
+            vec![polygon(&[(1, 2), (3, 4)]), polygon(&[(5, 6)])],
+        );
+    }
+
+    #[test]
+    fn parse_answer_malformed() {
+        for answer in ["canvas:x=10", "canvas:[{\"x\":1}]", "canvas:[[{\"y\":1}]]"] {
+            assert!(matches!(
+                Captcha::parse_answer(answer.to_string()),
+                Err(DecodeAnswerError::InvalidFormat { .. })
+            ), "{}", answer);
+        }
+    }
+
+    #[test]
+    fn parse_solution_canvas() {
+        let solution = serde_json::json!({ "canvas": [[{ "x": 1, "y": 2 }]] });
+        assert_eq!(Captcha::parse_solution(solution).unwrap(), vec![polygon(&[(1, 2)])]);
+    }
+}
@@ -14,9 +14,63 @@ pub struct CliArgs {
     /// 2captcha api result url
     #[structopt(long = "two-captcha-api-result-url", default_value = crate::API_RESULT_URL)]
     api_result_url: String,
-    /// 2captcha results poll interval timeout (in milliseconds)
-    #[structopt(long = "two-captcha-poll-timeout-ms", default_value = crate::DEFAULT_POLL_TIMEOUT_MS_STR)]
-    poll_timeout_ms: u64,
+    /// 2captcha api v2 createTask url
+    #[structopt(long = "two-captcha-api-create-task-url", default_value = crate::API_CREATE_TASK_URL)]
+    api_create_task_url: String,
+    /// 2captcha api v2 getTaskResult url
+    #[structopt(long = "two-captcha-api-get-task-result-url", default_value = crate::API_GET_TASK_RESULT_URL)]
+    api_get_task_result_url: String,
+    /// 2captcha api v2 reportCorrect url
+    #[structopt(long = "two-captcha-api-report-correct-url", default_value = crate::API_REPORT_CORRECT_URL)]
+    api_report_correct_url: String,
+    /// 2captcha api v2 reportIncorrect url
+    #[structopt(long = "two-captcha-api-report-incorrect-url", default_value = crate::API_REPORT_INCORRECT_URL)]
+    api_report_incorrect_url: String,
+    /// 2captcha api v2 getBalance url
+    #[structopt(long = "two-captcha-api-get-balance-url", default_value = crate::API_GET_BALANCE_URL)]
+    api_get_balance_url: String,
+    /// 2captcha api protocol ("legacy" for in.php/res.php or "v2" for createTask/getTaskResult)
+    #[structopt(long = "two-captcha-protocol", default_value = crate::DEFAULT_PROTOCOL_STR)]
+    protocol: crate::Protocol,
+    /// 2captcha delay before the first results poll (in milliseconds, defaults to the captcha type recommendation)
+    #[structopt(long = "two-captcha-initial-delay-ms")]
+    initial_delay_ms: Option<u64>,
+    /// 2captcha results poll interval (in milliseconds, defaults to the captcha type recommendation)
+    #[structopt(long = "two-captcha-poll-interval-ms")]
+    poll_interval_ms: Option<u64>,
+    /// grow the poll interval while the answer is not ready
+    #[structopt(long = "two-captcha-adaptive-poll")]
+    adaptive_poll: bool,
+    /// 2captcha upper bound for the adaptive poll interval (in milliseconds)
+    #[structopt(long = "two-captcha-max-poll-interval-ms", default_value = crate::DEFAULT_MAX_POLL_INTERVAL_MS_STR)]
+    max_poll_interval_ms: u64,
+    /// refuse to submit captchas when the account balance is below this amount
+    #[structopt(long = "two-captcha-min-balance")]
+    min_balance: Option<crate::Decimal>,
+    /// how long a fetched balance is reused by the low-balance check (in milliseconds)
+    #[structopt(long = "two-captcha-balance-cache-ttl-ms", default_value = crate::DEFAULT_BALANCE_CACHE_TTL_MS_STR)]
+    balance_cache_ttl_ms: u64,
+    /// pingback (callback) url 2captcha should notify when a captcha is solved
+    #[structopt(long = "two-captcha-pingback-url")]
+    pingback_url: Option<String>,
+    /// slow poll interval used alongside the pingback in case it never arrives (in milliseconds)
+    #[structopt(long = "two-captcha-pingback-fallback-poll-interval-ms", default_value = crate::DEFAULT_PINGBACK_FALLBACK_POLL_INTERVAL_MS_STR)]
+    pingback_fallback_poll_interval_ms: u64,
+    /// poll pending captchas in batches with a single `action=get&ids=...` request
+    #[structopt(long = "two-captcha-bulk-poll")]
+    bulk_poll: bool,
+    /// maximum attempts for submit and poll requests failing with transient errors
+    #[structopt(long = "two-captcha-retry-max-attempts", default_value = crate::retry::DEFAULT_RETRY_MAX_ATTEMPTS_STR)]
+    retry_max_attempts: u32,
+    /// initial retry backoff delay (in milliseconds)
+    #[structopt(long = "two-captcha-retry-initial-delay-ms", default_value = crate::retry::DEFAULT_RETRY_INITIAL_DELAY_MS_STR)]
+    retry_initial_delay_ms: u64,
+    /// maximum retry backoff delay (in milliseconds)
+    #[structopt(long = "two-captcha-retry-max-delay-ms", default_value = crate::retry::DEFAULT_RETRY_MAX_DELAY_MS_STR)]
+    retry_max_delay_ms: u64,
+    /// overall deadline for waiting on a captcha answer (in milliseconds, 0 disables it)
+    #[structopt(long = "two-captcha-solve-timeout-ms", default_value = crate::DEFAULT_SOLVE_TIMEOUT_MS_STR)]
+    solve_timeout_ms: u64,
 }
 
 impl AsRef<CliArgs> for CliArgs {
@@ -30,7 +84,29 @@ impl crate::Params {
         Self {
             api_request_url: cli_args.as_ref().api_request_url.clone(),
             api_result_url: cli_args.as_ref().api_result_url.clone(),
-            poll_timeout_ms: cli_args.as_ref().poll_timeout_ms,
+            api_create_task_url: cli_args.as_ref().api_create_task_url.clone(),
+            api_get_task_result_url: cli_args.as_ref().api_get_task_result_url.clone(),
+            api_report_correct_url: cli_args.as_ref().api_report_correct_url.clone(),
+            api_report_incorrect_url: cli_args.as_ref().api_report_incorrect_url.clone(),
+            api_get_balance_url: cli_args.as_ref().api_get_balance_url.clone(),
+            maybe_initial_delay_ms: cli_args.as_ref().initial_delay_ms,
+            maybe_poll_interval_ms: cli_args.as_ref().poll_interval_ms,
+            is_adaptive_poll: cli_args.as_ref().adaptive_poll,
+            max_poll_interval_ms: cli_args.as_ref().max_poll_interval_ms,
+            protocol: cli_args.as_ref().protocol,
+            maybe_min_balance: cli_args.as_ref().min_balance,
+            balance_cache_ttl_ms: cli_args.as_ref().balance_cache_ttl_ms,
+            maybe_pingback_url: cli_args.as_ref().pingback_url.clone(),
+            pingback_fallback_poll_interval_ms: cli_args.as_ref().pingback_fallback_poll_interval_ms,
+            is_bulk_poll: cli_args.as_ref().bulk_poll,
+            retry_policy: crate::retry::RetryPolicy {
+                max_attempts: cli_args.as_ref().retry_max_attempts,
+                initial_delay_ms: cli_args.as_ref().retry_initial_delay_ms,
+                max_delay_ms: cli_args.as_ref().retry_max_delay_ms,
+                ..Default::default()
+            },
+            maybe_solve_timeout_ms: Some(cli_args.as_ref().solve_timeout_ms)
+                .filter(|&solve_timeout_ms| solve_timeout_ms > 0),
         }
     }
 }
@@ -0,0 +1,228 @@
+use std::{
+    time::{
+        Duration,
+    },
+};
+
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    IMAGE_INITIAL_DELAY_MS,
+    IMAGE_POLL_INTERVAL_MS,
+    normal::{
+        CaptchaData,
+        ImageSource,
+        impl_image_setters,
+    },
+};
+
+pub use crate::normal::PrepareRequestError;
+
+pub struct Captcha {
+    captcha_data: CaptchaData,
+    maybe_text_instructions: Option<String>,
+    maybe_image_instructions: Option<CaptchaData>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    image: ImageSource,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Point {
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    InvalidBase64 { source: String, error: base64::DecodeError, },
+    CaptchaImageIsNotProvided,
+    InstructionsAreNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let ImageSource { maybe_captcha_data, maybe_text_instructions, maybe_image_instructions, } = self.image;
+        let captcha_data = maybe_captcha_data
+            .ok_or(BuilderError::CaptchaImageIsNotProvided)?;
+        if maybe_text_instructions.is_none() && maybe_image_instructions.is_none() {
+            return Err(BuilderError::InstructionsAreNotProvided);
+        }
+        Ok(Captcha {
+            captcha_data,
+            maybe_text_instructions,
+            maybe_image_instructions,
+        })
+    }
+}
+
+impl_image_setters!(CaptchaBuilder);
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    img_instructions: Option<String>,
+}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = Vec<Point>;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "coordinates";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        let mut params = vec![
+            ("coordinatescaptcha", "1".to_string()),
+        ];
+        if let Some(text_instructions) = &self.maybe_text_instructions {
+            params.push(("textinstructions", text_instructions.clone()));
+        }
+        let mut attachments = vec![];
+        if let Some(image_instructions) = &self.maybe_image_instructions {
+            attachments.push(("imginstructions", image_instructions));
+        }
+        self.captcha_data.prepare_request(None, params, attachments, api_token, request_builder).await
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: "CoordinatesTask",
+            body: self.captcha_data.to_base64().await?,
+            comment: self.maybe_text_instructions.clone(),
+            img_instructions: CaptchaData::maybe_to_base64(self.maybe_image_instructions.as_ref()).await?,
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        match parse_points(&answer) {
+            Some(points) =>
+                Ok(points),
+            None =>
+                Err(DecodeAnswerError::InvalidFormat { source: answer, }),
+        }
+    }
+
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        let maybe_points = solution.get("coordinates")
+            .and_then(|points| parse_points(&points.to_string()));
+        match maybe_points {
+            Some(points) =>
+                Ok(points),
+            None =>
+                Err(DecodeAnswerError::InvalidFormat { source: solution.to_string(), }),
+        }
+    }
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(IMAGE_INITIAL_DELAY_MS)
+    }
+
+    fn recommended_poll_interval() -> Duration {
+        Duration::from_millis(IMAGE_POLL_INTERVAL_MS)
+    }
+}
+
+pub(crate) fn parse_points(answer: &str) -> Option<Vec<Point>> {
+    if answer.starts_with('[') {
+        let values: Vec<serde_json::Value> = serde_json::from_str(answer).ok()?;
+        return values.iter()
+            .map(|value| Some(Point {
+                x: parse_coordinate(value.get("x")?)?,
+                y: parse_coordinate(value.get("y")?)?,
+            }))
+            .collect();
+    }
+
+    let points = answer.strip_prefix("coordinates:")
+        .unwrap_or(answer);
+    points.split(';')
+        .filter(|point| !point.is_empty())
+        .map(|point| {
+            let (x, y) = point.split_once(',')?;
+            Some(Point {
+                x: x.trim().strip_prefix("x=")?.parse().ok()?,
+                y: y.trim().strip_prefix("y=")?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn parse_coordinate(value: &serde_json::Value) -> Option<u32> {
+    match value {
+        serde_json::Value::String(string) =>
+            string.parse().ok(),
+        serde_json::Value::Number(number) =>
+            number.as_u64().and_then(|number| number.try_into().ok()),
+        _ =>
+            None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_answer_text_form() {
+        assert_eq!(
+            Captcha::parse_answer("coordinates:x=39,y=59;x=252,y=72".to_string()).unwrap(),
+            vec![Point { x: 39, y: 59, }, Point { x: 252, y: 72, }],
+        );
+    }
+
+    #[test]
+    fn parse_answer_json_array() {
+        assert_eq!(
+            Captcha::parse_answer(r#"[{"x":"39","y":"59"},{"x":252,"y":72}]"#.to_string()).unwrap(),
+            vec![Point { x: 39, y: 59, }, Point { x: 252, y: 72, }],
+        );
+    }
+
+    #[test]
+    fn parse_answer_malformed() {
+        for answer in ["coordinates:x=39", "coordinates:x=a,y=1", r#"[{"x":1}]"#, "[not json"] {
+            assert!(matches!(
+                Captcha::parse_answer(answer.to_string()),
+                Err(DecodeAnswerError::InvalidFormat { .. })
+            ), "{}", answer);
+        }
+    }
+
+    #[test]
+    fn parse_solution_coordinates() {
+        let solution = serde_json::json!({ "coordinates": [{ "x": 10, "y": 20 }] });
+        assert_eq!(Captcha::parse_solution(solution).unwrap(), vec![Point { x: 10, y: 20, }]);
+    }
+
+    #[test]
+    fn parse_solution_missing_field() {
+        let solution = serde_json::json!({ "click": [1] });
+        assert!(Captcha::parse_solution(solution).is_err());
+    }
+}
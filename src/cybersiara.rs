@@ -0,0 +1,148 @@
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    proxy::{
+        Proxy,
+        TaskProxy,
+    },
+};
+
+pub struct Captcha {
+    master_url_id: String,
+    pageurl: String,
+    user_agent: String,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_master_url_id: Option<String>,
+    maybe_pageurl: Option<String>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    MasterUrlIdIsNotProvided,
+    PageurlIsNotProvided,
+    UserAgentIsNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_master_url_id<T>(mut self, master_url_id: T) -> Self where T: AsRef<str> {
+        self.maybe_master_url_id = Some(master_url_id.as_ref().to_string());
+        self
+    }
+
+    pub fn set_pageurl<T>(mut self, pageurl: T) -> Self where T: AsRef<str> {
+        self.maybe_pageurl = Some(pageurl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_user_agent<T>(mut self, user_agent: T) -> Self where T: AsRef<str> {
+        self.maybe_user_agent = Some(user_agent.as_ref().to_string());
+        self
+    }
+
+    pub fn set_proxy(mut self, proxy: Proxy) -> Self {
+        self.maybe_proxy = Some(proxy);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let master_url_id = self.maybe_master_url_id
+            .filter(|master_url_id| !master_url_id.is_empty())
+            .ok_or(BuilderError::MasterUrlIdIsNotProvided)?;
+        let pageurl = self.maybe_pageurl
+            .filter(|pageurl| !pageurl.is_empty())
+            .ok_or(BuilderError::PageurlIsNotProvided)?;
+        let user_agent = self.maybe_user_agent
+            .filter(|user_agent| !user_agent.is_empty())
+            .ok_or(BuilderError::UserAgentIsNotProvided)?;
+        Ok(Captcha {
+            master_url_id,
+            pageurl,
+            user_agent,
+            maybe_proxy: self.maybe_proxy,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    #[serde(rename = "websiteURL")]
+    website_url: String,
+    #[serde(rename = "SlideMasterUrlId")]
+    slide_master_url_id: String,
+    user_agent: String,
+    #[serde(flatten)]
+    maybe_proxy: Option<TaskProxy>,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "cybersiara";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!("building cybersiara request for master_url_id = {} on {}", self.master_url_id, self.pageurl);
+
+        let mut form = vec![
+            ("method", "cybersiara".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("master_url_id", self.master_url_id.clone()),
+            ("pageurl", self.pageurl.clone()),
+            ("userAgent", self.user_agent.clone()),
+        ];
+        if let Some(proxy) = &self.maybe_proxy {
+            form.push(("proxy", proxy.proxy_param()));
+            form.push(("proxytype", proxy.proxy_type().as_str().to_string()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: if self.maybe_proxy.is_some() { "AntiCyberSiAraTask" } else { "AntiCyberSiAraTaskProxyless" },
+            website_url: self.pageurl.clone(),
+            slide_master_url_id: self.master_url_id.clone(),
+            user_agent: self.user_agent.clone(),
+            maybe_proxy: self.maybe_proxy.as_ref().map(Proxy::task_proxy),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "token")
+    }
+}
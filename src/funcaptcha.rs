@@ -0,0 +1,186 @@
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    proxy::{
+        Proxy,
+        TaskProxy,
+    },
+};
+
+pub struct Captcha {
+    publickey: String,
+    pageurl: String,
+    maybe_surl: Option<String>,
+    maybe_data_blob: Option<String>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_publickey: Option<String>,
+    maybe_pageurl: Option<String>,
+    maybe_surl: Option<String>,
+    maybe_data_blob: Option<String>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    PublickeyIsNotProvided,
+    InvalidPublickey { publickey: String, },
+    PageurlIsNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_publickey<T>(mut self, publickey: T) -> Self where T: AsRef<str> {
+        self.maybe_publickey = Some(publickey.as_ref().to_string());
+        self
+    }
+
+    pub fn set_pageurl<T>(mut self, pageurl: T) -> Self where T: AsRef<str> {
+        self.maybe_pageurl = Some(pageurl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_surl<T>(mut self, surl: T) -> Self where T: AsRef<str> {
+        self.maybe_surl = Some(surl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_data_blob<T>(mut self, data_blob: T) -> Self where T: AsRef<str> {
+        self.maybe_data_blob = Some(data_blob.as_ref().to_string());
+        self
+    }
+
+    pub fn set_user_agent<T>(mut self, user_agent: T) -> Self where T: AsRef<str> {
+        self.maybe_user_agent = Some(user_agent.as_ref().to_string());
+        self
+    }
+
+    pub fn set_proxy(mut self, proxy: Proxy) -> Self {
+        self.maybe_proxy = Some(proxy);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let publickey = self.maybe_publickey
+            .filter(|publickey| !publickey.is_empty())
+            .ok_or(BuilderError::PublickeyIsNotProvided)?;
+        if !is_uuid(&publickey) {
+            return Err(BuilderError::InvalidPublickey { publickey, });
+        }
+        let pageurl = self.maybe_pageurl
+            .filter(|pageurl| !pageurl.is_empty())
+            .ok_or(BuilderError::PageurlIsNotProvided)?;
+        Ok(Captcha {
+            publickey,
+            pageurl,
+            maybe_surl: self.maybe_surl,
+            maybe_data_blob: self.maybe_data_blob,
+            maybe_user_agent: self.maybe_user_agent,
+            maybe_proxy: self.maybe_proxy,
+        })
+    }
+}
+
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<_> = value.split('-').collect();
+    groups.len() == 5 && groups.iter()
+        .zip([8, 4, 4, 4, 12])
+        .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    #[serde(rename = "websiteURL")]
+    website_url: String,
+    website_public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "funcaptchaApiJSSubdomain")]
+    funcaptcha_api_js_subdomain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_agent: Option<String>,
+    #[serde(flatten)]
+    maybe_proxy: Option<TaskProxy>,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "funcaptcha";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!("building funcaptcha request for publickey = {} on {}", self.publickey, self.pageurl);
+
+        let mut form = vec![
+            ("method", "funcaptcha".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("publickey", self.publickey.clone()),
+            ("pageurl", self.pageurl.clone()),
+        ];
+        if let Some(surl) = &self.maybe_surl {
+            form.push(("surl", surl.clone()));
+        }
+        if let Some(data_blob) = &self.maybe_data_blob {
+            form.push(("data[blob]", data_blob.clone()));
+        }
+        if let Some(user_agent) = &self.maybe_user_agent {
+            form.push(("userAgent", user_agent.clone()));
+        }
+        if let Some(proxy) = &self.maybe_proxy {
+            form.push(("proxy", proxy.proxy_param()));
+            form.push(("proxytype", proxy.proxy_type().as_str().to_string()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: if self.maybe_proxy.is_some() { "FunCaptchaTask" } else { "FunCaptchaTaskProxyless" },
+            website_url: self.pageurl.clone(),
+            website_public_key: self.publickey.clone(),
+            funcaptcha_api_js_subdomain: self.maybe_surl.clone(),
+            data: self.maybe_data_blob.as_ref().map(|blob| serde_json::json!({ "blob": blob }).to_string()),
+            user_agent: self.maybe_user_agent.clone(),
+            maybe_proxy: self.maybe_proxy.as_ref().map(Proxy::task_proxy),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "token")
+    }
+}
@@ -0,0 +1,2 @@
+pub mod v3;
+pub mod v4;
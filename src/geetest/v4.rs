@@ -0,0 +1,163 @@
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+    Deserialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    proxy::{
+        Proxy,
+        TaskProxy,
+    },
+};
+
+pub struct Captcha {
+    captcha_id: String,
+    pageurl: String,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_captcha_id: Option<String>,
+    maybe_pageurl: Option<String>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Debug)]
+pub struct Answer {
+    pub captcha_id: String,
+    pub lot_number: String,
+    pub pass_token: String,
+    pub gen_time: String,
+    pub captcha_output: String,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    CaptchaIdIsNotProvided,
+    PageurlIsNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_captcha_id<T>(mut self, captcha_id: T) -> Self where T: AsRef<str> {
+        self.maybe_captcha_id = Some(captcha_id.as_ref().to_string());
+        self
+    }
+
+    pub fn set_pageurl<T>(mut self, pageurl: T) -> Self where T: AsRef<str> {
+        self.maybe_pageurl = Some(pageurl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_user_agent<T>(mut self, user_agent: T) -> Self where T: AsRef<str> {
+        self.maybe_user_agent = Some(user_agent.as_ref().to_string());
+        self
+    }
+
+    pub fn set_proxy(mut self, proxy: Proxy) -> Self {
+        self.maybe_proxy = Some(proxy);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let captcha_id = self.maybe_captcha_id
+            .filter(|captcha_id| !captcha_id.is_empty())
+            .ok_or(BuilderError::CaptchaIdIsNotProvided)?;
+        let pageurl = self.maybe_pageurl
+            .filter(|pageurl| !pageurl.is_empty())
+            .ok_or(BuilderError::PageurlIsNotProvided)?;
+        Ok(Captcha {
+            captcha_id,
+            pageurl,
+            maybe_user_agent: self.maybe_user_agent,
+            maybe_proxy: self.maybe_proxy,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct InitParameters {
+    captcha_id: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    #[serde(rename = "websiteURL")]
+    website_url: String,
+    version: u8,
+    init_parameters: InitParameters,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_agent: Option<String>,
+    #[serde(flatten)]
+    maybe_proxy: Option<TaskProxy>,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = Answer;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "geetest_v4";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!("building geetest_v4 request for captcha_id = {} on {}", self.captcha_id, self.pageurl);
+
+        let mut form = vec![
+            ("method", "geetest_v4".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("captcha_id", self.captcha_id.clone()),
+            ("pageurl", self.pageurl.clone()),
+        ];
+        if let Some(user_agent) = &self.maybe_user_agent {
+            form.push(("userAgent", user_agent.clone()));
+        }
+        if let Some(proxy) = &self.maybe_proxy {
+            form.push(("proxy", proxy.proxy_param()));
+            form.push(("proxytype", proxy.proxy_type().as_str().to_string()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: if self.maybe_proxy.is_some() { "GeeTestTask" } else { "GeeTestTaskProxyless" },
+            website_url: self.pageurl.clone(),
+            version: 4,
+            init_parameters: InitParameters { captcha_id: self.captcha_id.clone(), },
+            user_agent: self.maybe_user_agent.clone(),
+            maybe_proxy: self.maybe_proxy.as_ref().map(Proxy::task_proxy),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::from_json(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::from_solution(solution)
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    time::{
+        Duration,
+    },
+};
+
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    IMAGE_INITIAL_DELAY_MS,
+    IMAGE_POLL_INTERVAL_MS,
+    normal::{
+        CaptchaData,
+        ImageSource,
+        impl_image_setters,
+    },
+};
+
+pub use crate::normal::PrepareRequestError;
+
+pub struct Captcha {
+    captcha_data: CaptchaData,
+    maybe_rows: Option<u8>,
+    maybe_cols: Option<u8>,
+    maybe_text_instructions: Option<String>,
+    maybe_image_instructions: Option<CaptchaData>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    image: ImageSource,
+    maybe_rows: Option<u8>,
+    maybe_cols: Option<u8>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    InvalidBase64 { source: String, error: base64::DecodeError, },
+    CaptchaImageIsNotProvided,
+    InstructionsAreNotProvided,
+    InvalidGridSize { rows: u8, cols: u8, },
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_grid_size(mut self, rows: u8, cols: u8) -> Self {
+        self.maybe_rows = Some(rows);
+        self.maybe_cols = Some(cols);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let ImageSource { maybe_captcha_data, maybe_text_instructions, maybe_image_instructions, } = self.image;
+        let captcha_data = maybe_captcha_data
+            .ok_or(BuilderError::CaptchaImageIsNotProvided)?;
+        if maybe_text_instructions.is_none() && maybe_image_instructions.is_none() {
+            return Err(BuilderError::InstructionsAreNotProvided);
+        }
+        if let (Some(rows), Some(cols)) = (self.maybe_rows, self.maybe_cols) {
+            if rows == 0 || cols == 0 {
+                return Err(BuilderError::InvalidGridSize { rows, cols, });
+            }
+        }
+        Ok(Captcha {
+            captcha_data,
+            maybe_rows: self.maybe_rows,
+            maybe_cols: self.maybe_cols,
+            maybe_text_instructions,
+            maybe_image_instructions,
+        })
+    }
+}
+
+impl_image_setters!(CaptchaBuilder);
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rows: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    columns: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    img_instructions: Option<String>,
+}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = Vec<u8>;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "grid";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        let mut params = vec![
+            ("recaptcha", "1".to_string()),
+        ];
+        if let Some(rows) = self.maybe_rows {
+            params.push(("recaptcharows", rows.to_string()));
+        }
+        if let Some(cols) = self.maybe_cols {
+            params.push(("recaptchacols", cols.to_string()));
+        }
+        if let Some(text_instructions) = &self.maybe_text_instructions {
+            params.push(("textinstructions", text_instructions.clone()));
+        }
+        let mut attachments = vec![];
+        if let Some(image_instructions) = &self.maybe_image_instructions {
+            attachments.push(("imginstructions", image_instructions));
+        }
+        self.captcha_data.prepare_request(None, params, attachments, api_token, request_builder).await
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: "GridTask",
+            body: self.captcha_data.to_base64().await?,
+            rows: self.maybe_rows,
+            columns: self.maybe_cols,
+            comment: self.maybe_text_instructions.clone(),
+            img_instructions: CaptchaData::maybe_to_base64(self.maybe_image_instructions.as_ref()).await?,
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        let cells = answer.strip_prefix("click:")
+            .unwrap_or(&answer);
+        cells.split('/')
+            .filter(|cell| !cell.is_empty())
+            .map(|cell| cell.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| DecodeAnswerError::InvalidFormat { source: answer.clone(), })
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        let cells = solution.get("click")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+        DecodeAnswerError::from_solution(cells)
+    }
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(IMAGE_INITIAL_DELAY_MS)
+    }
+
+    fn recommended_poll_interval() -> Duration {
+        Duration::from_millis(IMAGE_POLL_INTERVAL_MS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_answer_click() {
+        assert_eq!(Captcha::parse_answer("click:1/4/7".to_string()).unwrap(), vec![1, 4, 7]);
+    }
+
+    #[test]
+    fn parse_answer_without_prefix() {
+        assert_eq!(Captcha::parse_answer("2/3".to_string()).unwrap(), vec![2, 3]);
+    }
+
+    #[test]
+    fn parse_answer_malformed() {
+        assert!(matches!(
+            Captcha::parse_answer("click:1/x/7".to_string()),
+            Err(DecodeAnswerError::InvalidFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_solution_click() {
+        let solution = serde_json::json!({ "click": [3, 6, 9] });
+        assert_eq!(Captcha::parse_solution(solution).unwrap(), vec![3, 6, 9]);
+    }
+
+    #[test]
+    fn parse_solution_malformed() {
+        let solution = serde_json::json!({ "text": "1/2" });
+        assert!(Captcha::parse_solution(solution).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    proxy::{
+        Proxy,
+        TaskProxy,
+    },
+};
+
+pub struct Captcha {
+    sitekey: String,
+    pageurl: String,
+    is_invisible: bool,
+    maybe_data: Option<String>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_sitekey: Option<String>,
+    maybe_pageurl: Option<String>,
+    is_invisible: bool,
+    maybe_data: Option<String>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    SitekeyIsNotProvided,
+    PageurlIsNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_sitekey<T>(mut self, sitekey: T) -> Self where T: AsRef<str> {
+        self.maybe_sitekey = Some(sitekey.as_ref().to_string());
+        self
+    }
+
+    pub fn set_pageurl<T>(mut self, pageurl: T) -> Self where T: AsRef<str> {
+        self.maybe_pageurl = Some(pageurl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_invisible(mut self, is_invisible: bool) -> Self {
+        self.is_invisible = is_invisible;
+        self
+    }
+
+    pub fn set_enterprise_data<T>(mut self, data: T) -> Self where T: AsRef<str> {
+        self.maybe_data = Some(data.as_ref().to_string());
+        self
+    }
+
+    pub fn set_user_agent<T>(mut self, user_agent: T) -> Self where T: AsRef<str> {
+        self.maybe_user_agent = Some(user_agent.as_ref().to_string());
+        self
+    }
+
+    pub fn set_proxy(mut self, proxy: Proxy) -> Self {
+        self.maybe_proxy = Some(proxy);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let sitekey = self.maybe_sitekey
+            .filter(|sitekey| !sitekey.is_empty())
+            .ok_or(BuilderError::SitekeyIsNotProvided)?;
+        let pageurl = self.maybe_pageurl
+            .filter(|pageurl| !pageurl.is_empty())
+            .ok_or(BuilderError::PageurlIsNotProvided)?;
+        Ok(Captcha {
+            sitekey,
+            pageurl,
+            is_invisible: self.is_invisible,
+            maybe_data: self.maybe_data,
+            maybe_user_agent: self.maybe_user_agent,
+            maybe_proxy: self.maybe_proxy,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct EnterprisePayload {
+    rqdata: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    #[serde(rename = "websiteURL")]
+    website_url: String,
+    website_key: String,
+    is_invisible: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enterprise_payload: Option<EnterprisePayload>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_agent: Option<String>,
+    #[serde(flatten)]
+    maybe_proxy: Option<TaskProxy>,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "hcaptcha";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!("building hcaptcha request for sitekey = {} on {}", self.sitekey, self.pageurl);
+
+        let mut form = vec![
+            ("method", "hcaptcha".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("sitekey", self.sitekey.clone()),
+            ("pageurl", self.pageurl.clone()),
+        ];
+        if self.is_invisible {
+            form.push(("invisible", "1".to_string()));
+        }
+        if let Some(data) = &self.maybe_data {
+            form.push(("data", data.clone()));
+        }
+        if let Some(user_agent) = &self.maybe_user_agent {
+            form.push(("userAgent", user_agent.clone()));
+        }
+        if let Some(proxy) = &self.maybe_proxy {
+            form.push(("proxy", proxy.proxy_param()));
+            form.push(("proxytype", proxy.proxy_type().as_str().to_string()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: if self.maybe_proxy.is_some() { "HCaptchaTask" } else { "HCaptchaTaskProxyless" },
+            website_url: self.pageurl.clone(),
+            website_key: self.sitekey.clone(),
+            is_invisible: self.is_invisible,
+            enterprise_payload: self.maybe_data.clone().map(|rqdata| EnterprisePayload { rqdata, }),
+            user_agent: self.maybe_user_agent.clone(),
+            maybe_proxy: self.maybe_proxy.as_ref().map(Proxy::task_proxy),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "token")
+    }
+
+    fn returns_user_agent() -> bool {
+        true
+    }
+}
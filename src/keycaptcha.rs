@@ -0,0 +1,181 @@
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    proxy::{
+        Proxy,
+        TaskProxy,
+    },
+};
+
+pub struct Captcha {
+    user_id: String,
+    session_id: String,
+    web_server_sign: String,
+    web_server_sign2: String,
+    pageurl: String,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_user_id: Option<String>,
+    maybe_session_id: Option<String>,
+    maybe_web_server_sign: Option<String>,
+    maybe_web_server_sign2: Option<String>,
+    maybe_pageurl: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    UserIdIsNotProvided,
+    SessionIdIsNotProvided,
+    WebServerSignIsNotProvided,
+    WebServerSign2IsNotProvided,
+    PageurlIsNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_user_id<T>(mut self, user_id: T) -> Self where T: AsRef<str> {
+        self.maybe_user_id = Some(user_id.as_ref().to_string());
+        self
+    }
+
+    pub fn set_session_id<T>(mut self, session_id: T) -> Self where T: AsRef<str> {
+        self.maybe_session_id = Some(session_id.as_ref().to_string());
+        self
+    }
+
+    pub fn set_web_server_sign<T>(mut self, web_server_sign: T) -> Self where T: AsRef<str> {
+        self.maybe_web_server_sign = Some(web_server_sign.as_ref().to_string());
+        self
+    }
+
+    pub fn set_web_server_sign2<T>(mut self, web_server_sign2: T) -> Self where T: AsRef<str> {
+        self.maybe_web_server_sign2 = Some(web_server_sign2.as_ref().to_string());
+        self
+    }
+
+    pub fn set_pageurl<T>(mut self, pageurl: T) -> Self where T: AsRef<str> {
+        self.maybe_pageurl = Some(pageurl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_proxy(mut self, proxy: Proxy) -> Self {
+        self.maybe_proxy = Some(proxy);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let user_id = self.maybe_user_id
+            .filter(|user_id| !user_id.is_empty())
+            .ok_or(BuilderError::UserIdIsNotProvided)?;
+        let session_id = self.maybe_session_id
+            .filter(|session_id| !session_id.is_empty())
+            .ok_or(BuilderError::SessionIdIsNotProvided)?;
+        let web_server_sign = self.maybe_web_server_sign
+            .filter(|web_server_sign| !web_server_sign.is_empty())
+            .ok_or(BuilderError::WebServerSignIsNotProvided)?;
+        let web_server_sign2 = self.maybe_web_server_sign2
+            .filter(|web_server_sign2| !web_server_sign2.is_empty())
+            .ok_or(BuilderError::WebServerSign2IsNotProvided)?;
+        let pageurl = self.maybe_pageurl
+            .filter(|pageurl| !pageurl.is_empty())
+            .ok_or(BuilderError::PageurlIsNotProvided)?;
+        Ok(Captcha {
+            user_id,
+            session_id,
+            web_server_sign,
+            web_server_sign2,
+            pageurl,
+            maybe_proxy: self.maybe_proxy,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    #[serde(rename = "websiteURL")]
+    website_url: String,
+    #[serde(rename = "s_s_c_user_id")]
+    user_id: String,
+    #[serde(rename = "s_s_c_session_id")]
+    session_id: String,
+    #[serde(rename = "s_s_c_web_server_sign")]
+    web_server_sign: String,
+    #[serde(rename = "s_s_c_web_server_sign2")]
+    web_server_sign2: String,
+    #[serde(flatten)]
+    maybe_proxy: Option<TaskProxy>,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "keycaptcha";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!("building keycaptcha request for user_id = {} on {}", self.user_id, self.pageurl);
+
+        let mut form = vec![
+            ("method", "keycaptcha".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("s_s_c_user_id", self.user_id.clone()),
+            ("s_s_c_session_id", self.session_id.clone()),
+            ("s_s_c_web_server_sign", self.web_server_sign.clone()),
+            ("s_s_c_web_server_sign2", self.web_server_sign2.clone()),
+            ("pageurl", self.pageurl.clone()),
+        ];
+        if let Some(proxy) = &self.maybe_proxy {
+            form.push(("proxy", proxy.proxy_param()));
+            form.push(("proxytype", proxy.proxy_type().as_str().to_string()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: if self.maybe_proxy.is_some() { "KeyCaptchaTask" } else { "KeyCaptchaTaskProxyless" },
+            website_url: self.pageurl.clone(),
+            user_id: self.user_id.clone(),
+            session_id: self.session_id.clone(),
+            web_server_sign: self.web_server_sign.clone(),
+            web_server_sign2: self.web_server_sign2.clone(),
+            maybe_proxy: self.maybe_proxy.as_ref().map(Proxy::task_proxy),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "token")
+    }
+}
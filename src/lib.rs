@@ -1,13 +1,23 @@
 #![forbid(unsafe_code)]
 
 use std::{
+    fmt,
+    marker::PhantomData,
     time::{
         Instant,
         Duration,
+        SystemTime,
     },
+    future::pending,
+};
+
+use serde::{
+    Deserializer,
+    de::DeserializeOwned,
 };
 
 use serde_derive::{
+    Serialize,
     Deserialize,
 };
 
@@ -20,6 +30,7 @@ use reqwest::{
 use tokio::{
     time::{
         sleep,
+        sleep_until,
     },
 };
 
@@ -27,19 +38,105 @@ use async_trait::{
     async_trait,
 };
 
+use futures::{
+    FutureExt,
+};
+
+pub use rust_decimal::Decimal;
+pub use tokio_util::sync::CancellationToken;
+
+pub mod proxy;
 pub mod normal;
+pub mod recaptcha_v2;
+pub mod recaptcha_v3;
+pub mod hcaptcha;
+pub mod turnstile;
+pub mod geetest;
+pub mod funcaptcha;
+pub mod grid;
+pub mod coordinates;
+pub mod rotate;
+pub mod text;
+pub mod keycaptcha;
+pub mod capy;
+pub mod lemin;
+pub mod amazon_waf;
+pub mod mtcaptcha;
+pub mod yandex;
+pub mod tencent;
+pub mod cybersiara;
+pub mod audio;
+pub mod canvas;
+pub mod task;
+pub mod pingback;
+pub mod bulk_poll;
+pub mod retry;
 pub mod cli_args;
 
-pub const API_REQUEST_URL: &'static str = "http://2captcha.com/in.php";
-pub const API_RESULT_URL: &'static str = "http://2captcha.com/res.php";
-pub const DEFAULT_POLL_TIMEOUT_MS: u64 = 5000;
-pub const DEFAULT_POLL_TIMEOUT_MS_STR: &'static str = "5000";
+pub const API_REQUEST_URL: &str = "http://2captcha.com/in.php";
+pub const API_RESULT_URL: &str = "http://2captcha.com/res.php";
+pub const API_CREATE_TASK_URL: &str = "https://api.2captcha.com/createTask";
+pub const API_GET_TASK_RESULT_URL: &str = "https://api.2captcha.com/getTaskResult";
+pub const API_REPORT_CORRECT_URL: &str = "https://api.2captcha.com/reportCorrect";
+pub const API_REPORT_INCORRECT_URL: &str = "https://api.2captcha.com/reportIncorrect";
+pub const API_GET_BALANCE_URL: &str = "https://api.2captcha.com/getBalance";
+pub const DEFAULT_PROTOCOL_STR: &str = "legacy";
+pub const IMAGE_INITIAL_DELAY_MS: u64 = 5000;
+pub const TOKEN_INITIAL_DELAY_MS: u64 = 15000;
+pub const RECAPTCHA_INITIAL_DELAY_MS: u64 = 20000;
+pub const IMAGE_POLL_INTERVAL_MS: u64 = 5000;
+pub const TOKEN_POLL_INTERVAL_MS: u64 = 10000;
+pub const DEFAULT_MAX_POLL_INTERVAL_MS: u64 = 30000;
+pub const DEFAULT_MAX_POLL_INTERVAL_MS_STR: &str = "30000";
+pub const DEFAULT_PINGBACK_FALLBACK_POLL_INTERVAL_MS: u64 = 30000;
+pub const DEFAULT_PINGBACK_FALLBACK_POLL_INTERVAL_MS_STR: &str = "30000";
+pub const DEFAULT_BALANCE_CACHE_TTL_MS: u64 = 30000;
+pub const DEFAULT_BALANCE_CACHE_TTL_MS_STR: &str = "30000";
+pub const DEFAULT_SOLVE_TIMEOUT_MS: u64 = 300000;
+pub const DEFAULT_SOLVE_TIMEOUT_MS_STR: &str = "300000";
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Protocol {
+    Legacy,
+    JsonV2,
+}
+
+impl std::str::FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Protocol, String> {
+        match s {
+            "legacy" =>
+                Ok(Protocol::Legacy),
+            "v2" =>
+                Ok(Protocol::JsonV2),
+            other =>
+                Err(format!("unknown protocol {:?}, expected \"legacy\" or \"v2\"", other)),
+        }
+    }
+}
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Params {
     pub api_request_url: String,
     pub api_result_url: String,
-    pub poll_timeout_ms: u64,
+    pub api_create_task_url: String,
+    pub api_get_task_result_url: String,
+    pub api_report_correct_url: String,
+    pub api_report_incorrect_url: String,
+    pub api_get_balance_url: String,
+    pub maybe_initial_delay_ms: Option<u64>,
+    pub maybe_poll_interval_ms: Option<u64>,
+    pub is_adaptive_poll: bool,
+    pub max_poll_interval_ms: u64,
+    pub protocol: Protocol,
+    pub maybe_min_balance: Option<Decimal>,
+    pub balance_cache_ttl_ms: u64,
+    pub maybe_pingback_url: Option<String>,
+    pub pingback_fallback_poll_interval_ms: u64,
+    pub is_bulk_poll: bool,
+    pub retry_policy: retry::RetryPolicy,
+    pub maybe_solve_timeout_ms: Option<u64>,
 }
 
 impl Default for Params {
@@ -47,7 +144,23 @@ impl Default for Params {
         Params {
             api_request_url: API_REQUEST_URL.into(),
             api_result_url: API_RESULT_URL.into(),
-            poll_timeout_ms: DEFAULT_POLL_TIMEOUT_MS,
+            api_create_task_url: API_CREATE_TASK_URL.into(),
+            api_get_task_result_url: API_GET_TASK_RESULT_URL.into(),
+            api_report_correct_url: API_REPORT_CORRECT_URL.into(),
+            api_report_incorrect_url: API_REPORT_INCORRECT_URL.into(),
+            api_get_balance_url: API_GET_BALANCE_URL.into(),
+            maybe_initial_delay_ms: None,
+            maybe_poll_interval_ms: None,
+            is_adaptive_poll: false,
+            max_poll_interval_ms: DEFAULT_MAX_POLL_INTERVAL_MS,
+            protocol: Protocol::Legacy,
+            maybe_min_balance: None,
+            balance_cache_ttl_ms: DEFAULT_BALANCE_CACHE_TTL_MS,
+            maybe_pingback_url: None,
+            pingback_fallback_poll_interval_ms: DEFAULT_PINGBACK_FALLBACK_POLL_INTERVAL_MS,
+            is_bulk_poll: false,
+            retry_policy: retry::RetryPolicy::default(),
+            maybe_solve_timeout_ms: Some(DEFAULT_SOLVE_TIMEOUT_MS),
         }
     }
 }
@@ -65,16 +178,127 @@ impl From<String> for ApiToken {
 pub struct Api {
     api_token: ApiToken,
     params: Params,
+    client: Client,
+    maybe_pingback_listener: Option<pingback::PingbackListener>,
+    bulk_poller: bulk_poll::BulkPoller,
+    balance_cache: tokio::sync::Mutex<Option<(Instant, Decimal)>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub struct CaptchaId(String);
+
+impl CaptchaId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for CaptchaId {
+    fn from(captcha_id: String) -> CaptchaId {
+        CaptchaId(captcha_id)
+    }
+}
+
+impl fmt::Display for CaptchaId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
-pub struct Solved {
-    answer: String,
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "")]
+pub struct CaptchaTicket<C> {
+    captcha_id: CaptchaId,
+    submitted_at: SystemTime,
+    captcha_type: String,
+    protocol: Protocol,
+    #[serde(skip)]
+    _marker: PhantomData<fn() -> C>,
 }
 
-impl Solved {
-    pub fn answer(&self) -> &str {
+impl<C> Clone for CaptchaTicket<C> {
+    fn clone(&self) -> Self {
+        CaptchaTicket {
+            captcha_id: self.captcha_id.clone(),
+            submitted_at: self.submitted_at,
+            captcha_type: self.captcha_type.clone(),
+            protocol: self.protocol,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C> CaptchaTicket<C> {
+    pub fn captcha_id(&self) -> &CaptchaId {
+        &self.captcha_id
+    }
+
+    pub fn submitted_at(&self) -> SystemTime {
+        self.submitted_at
+    }
+
+    pub fn captcha_type(&self) -> &str {
+        &self.captcha_type
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+}
+
+impl<C> CaptchaTicket<C> where C: CaptchaRequest {
+    fn check_captcha_type(&self) -> Result<(), ApiError<C::PrepareRequestError>> {
+        if self.captcha_type != C::CAPTCHA_TYPE {
+            return Err(ApiError::CaptchaTypeMismatch { expected: C::CAPTCHA_TYPE, found: self.captcha_type.clone(), });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct SolveOptions {
+    maybe_timeout: Option<Duration>,
+    maybe_cancellation_token: Option<CancellationToken>,
+}
+
+impl SolveOptions {
+    pub fn new() -> SolveOptions {
+        SolveOptions::default()
+    }
+
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.maybe_timeout = Some(timeout);
+        self
+    }
+
+    pub fn set_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.maybe_cancellation_token = Some(cancellation_token);
+        self
+    }
+}
+
+pub struct Solved<A = String> {
+    captcha_id: CaptchaId,
+    answer: A,
+    maybe_user_agent: Option<String>,
+}
+
+impl<A> Solved<A> {
+    pub fn captcha_id(&self) -> &CaptchaId {
+        &self.captcha_id
+    }
+
+    pub fn answer(&self) -> &A {
         &self.answer
     }
+
+    pub fn into_answer(self) -> A {
+        self.answer
+    }
+
+    pub fn user_agent(&self) -> Option<&str> {
+        self.maybe_user_agent.as_deref()
+    }
 }
 
 #[derive(Debug)]
@@ -90,20 +314,466 @@ pub enum ApiError<E> {
     SendPollRequestBadStatusCode { status_code: StatusCode, },
     ReadPollResponse(reqwest::Error),
     DecodePollResponse(DecodeApiResponse),
+    DecodeAnswer(DecodeAnswerError),
+    TaskResponse(task::TaskResponseError),
+    SubmitTimeout,
+    SubmitCancelled,
+    Timeout { captcha_id: CaptchaId, },
+    Cancelled { captcha_id: CaptchaId, },
+    Balance(BalanceError),
+    LowBalance { balance: Decimal, min_balance: Decimal, },
+    CaptchaTypeMismatch { expected: &'static str, found: String, },
+}
+
+#[derive(Debug)]
+pub enum BalanceError {
+    SendBalanceRequest(reqwest::Error),
+    SendBalanceRequestBadStatusCode { status_code: StatusCode, },
+    ReadBalanceResponse(reqwest::Error),
+    DecodeBalanceResponse(DecodeApiResponse),
+    BalanceResponse(BalanceResponseError),
+}
+
+#[derive(Debug)]
+pub enum BalanceResponseError {
+    PollResponse(PollResponseError),
+    TaskResponse(task::TaskResponseError),
+    InvalidAmount { source: String, error: rust_decimal::Error, },
+    UnexpectedResponse { request: String, },
+}
+
+#[derive(Debug)]
+pub enum PingbackError {
+    SendPingbackRequest(reqwest::Error),
+    SendPingbackRequestBadStatusCode { status_code: StatusCode, },
+    ReadPingbackResponse(reqwest::Error),
+    DecodePingbackResponse(DecodeApiResponse),
+    PingbackResponse(PollResponseError),
+}
+
+#[derive(Debug)]
+pub enum ReportError {
+    SendReportRequest(reqwest::Error),
+    SendReportRequestBadStatusCode { status_code: StatusCode, },
+    ReadReportResponse(reqwest::Error),
+    DecodeReportResponse(DecodeApiResponse),
+    ReportResponse(ReportResponseError),
+}
+
+#[derive(Debug)]
+pub enum ReportResponseError {
+    ReportNotRecorded,
+    DuplicateReport,
+    PollResponse(PollResponseError),
+    TaskResponse(task::TaskResponseError),
+    UnexpectedResponse { request: String, },
 }
 
 impl Api {
     pub fn new(api_token: ApiToken, params: Params) -> Api {
-        Api { api_token, params, }
+        let client = Client::new();
+        let bulk_poller = bulk_poll::BulkPoller::new(
+            client.clone(),
+            api_token.key.clone(),
+            params.api_result_url.clone(),
+            params.retry_policy.clone(),
+        );
+        Api { api_token, params, client, maybe_pingback_listener: None, bulk_poller, balance_cache: tokio::sync::Mutex::new(None), }
+    }
+
+    pub fn set_pingback_listener(mut self, pingback_listener: pingback::PingbackListener) -> Self {
+        self.maybe_pingback_listener = Some(pingback_listener);
+        self
+    }
+
+    pub async fn solve<C>(&self, captcha: &C) -> Result<Solved<C::Answer>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        self.solve_with_options(captcha, &SolveOptions::default()).await
     }
 
-    pub async fn solve<C>(&self, captcha: &C) -> Result<Solved, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+    pub async fn solve_with_options<C>(&self, captcha: &C, options: &SolveOptions) -> Result<Solved<C::Answer>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        let maybe_deadline = self.solve_deadline(options);
+        let maybe_cancellation_token = options.maybe_cancellation_token.as_ref();
+        let ticket = self.submit_until(captcha, maybe_deadline, maybe_cancellation_token).await?;
+        self.wait_until(&ticket, maybe_deadline, maybe_cancellation_token).await
+    }
+
+    pub async fn submit<C>(&self, captcha: &C) -> Result<CaptchaTicket<C>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        self.submit_until(captcha, None, None).await
+    }
+
+    async fn submit_until<C>(&self, captcha: &C, maybe_deadline: Option<Instant>, maybe_cancellation_token: Option<&CancellationToken>) -> Result<CaptchaTicket<C>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        let interrupted = || async {
+            match interruption(maybe_deadline, maybe_cancellation_token).await {
+                Interruption::Timeout =>
+                    ApiError::SubmitTimeout,
+                Interruption::Cancelled =>
+                    ApiError::SubmitCancelled,
+            }
+        };
+        if let Some(error) = interrupted().now_or_never() {
+            return Err(error);
+        }
+
+        if let Some(min_balance) = self.params.maybe_min_balance {
+            let balance = tokio::select! {
+                error = interrupted() =>
+                    return Err(error),
+                result = self.cached_balance() =>
+                    result?,
+            };
+            if balance < min_balance {
+                return Err(ApiError::LowBalance { balance, min_balance, });
+            }
+        }
+
+        // a sent submit request is always awaited: the server may have accepted (and charged for) the captcha
+        let captcha_id = self.params.retry_policy
+            .run_until(
+                || async {
+                    match self.params.protocol {
+                        Protocol::Legacy =>
+                            self.submit_legacy(captcha).await,
+                        Protocol::JsonV2 =>
+                            self.submit_task(captcha).await,
+                    }
+                },
+                interrupted(),
+            )
+            .await?;
+
+        log::debug!("request finished, captcha id = {}", captcha_id);
+
+        Ok(CaptchaTicket {
+            captcha_id: CaptchaId(captcha_id),
+            submitted_at: SystemTime::now(),
+            captcha_type: C::CAPTCHA_TYPE.to_string(),
+            protocol: self.params.protocol,
+            _marker: PhantomData,
+        })
+    }
+
+    pub async fn poll<C>(&self, ticket: &CaptchaTicket<C>) -> Result<Option<Solved<C::Answer>>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        ticket.check_captcha_type()?;
+        self.params.retry_policy
+            .run(|| async {
+                match ticket.protocol {
+                    Protocol::Legacy =>
+                        self.poll_legacy::<C>(&ticket.captcha_id).await,
+                    Protocol::JsonV2 =>
+                        self.poll_task::<C>(&ticket.captcha_id).await,
+                }
+            })
+            .await
+    }
+
+    pub async fn wait<C>(&self, ticket: &CaptchaTicket<C>) -> Result<Solved<C::Answer>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        self.wait_with_options(ticket, &SolveOptions::default()).await
+    }
+
+    pub async fn wait_with_options<C>(&self, ticket: &CaptchaTicket<C>, options: &SolveOptions) -> Result<Solved<C::Answer>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        ticket.check_captcha_type()?;
+        let maybe_deadline = self.solve_deadline(options);
+        self.wait_until(ticket, maybe_deadline, options.maybe_cancellation_token.as_ref()).await
+    }
+
+    fn solve_deadline(&self, options: &SolveOptions) -> Option<Instant> {
+        options.maybe_timeout
+            .or_else(|| self.params.maybe_solve_timeout_ms.map(Duration::from_millis))
+            .map(|timeout| Instant::now() + timeout)
+    }
+
+    async fn wait_until<C>(&self, ticket: &CaptchaTicket<C>, maybe_deadline: Option<Instant>, maybe_cancellation_token: Option<&CancellationToken>) -> Result<Solved<C::Answer>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        tokio::select! {
+            biased;
+            interruption = interruption(maybe_deadline, maybe_cancellation_token) => {
+                if let Some(pingback_listener) = &self.maybe_pingback_listener {
+                    pingback_listener.unregister(&ticket.captcha_id);
+                }
+                let captcha_id = ticket.captcha_id.clone();
+                match interruption {
+                    Interruption::Timeout => {
+                        log::debug!("deadline exceeded while waiting for captcha id = {}", captcha_id);
+                        Err(ApiError::Timeout { captcha_id, })
+                    },
+                    Interruption::Cancelled => {
+                        log::debug!("cancelled while waiting for captcha id = {}", captcha_id);
+                        Err(ApiError::Cancelled { captcha_id, })
+                    },
+                }
+            },
+            result = self.wait_for_answer(ticket) =>
+                result,
+        }
+    }
+
+    async fn wait_for_answer<C>(&self, ticket: &CaptchaTicket<C>) -> Result<Solved<C::Answer>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        let initial_delay = self.params.maybe_initial_delay_ms
+            .map(Duration::from_millis)
+            .unwrap_or_else(C::recommended_initial_delay);
+        let poll_interval = self.params.maybe_poll_interval_ms
+            .map(Duration::from_millis)
+            .unwrap_or_else(C::recommended_poll_interval);
+
+        if let (Some(pingback_listener), Some(..)) = (&self.maybe_pingback_listener, &self.params.maybe_pingback_url) {
+            log::debug!("waiting for pingback with captcha id = {}", ticket.captcha_id);
+
+            // keep polling slowly in case the pingback never reaches us
+            let fallback_poll_interval = Duration::from_millis(self.params.pingback_fallback_poll_interval_ms)
+                .max(poll_interval);
+            let receiver = pingback_listener.register(&ticket.captcha_id);
+            let maybe_code = tokio::select! {
+                pingback = receiver =>
+                    pingback.ok(),
+                result = self.poll_until_solved(ticket, initial_delay, fallback_poll_interval) => {
+                    pingback_listener.unregister(&ticket.captcha_id);
+                    return result;
+                },
+            };
+            match maybe_code {
+                Some(code) => {
+                    let maybe_solved = match ticket.protocol {
+                        Protocol::Legacy =>
+                            solved_from_poll_result::<C>(&ticket.captcha_id, ApiResponse::from_code(code))?,
+                        Protocol::JsonV2 =>
+                            self.poll(ticket).await?,
+                    };
+                    if let Some(solved) = maybe_solved {
+                        return Ok(solved);
+                    }
+                },
+                None => {
+                    pingback_listener.unregister(&ticket.captcha_id);
+                    log::warn!("pingback for captcha id = {} was dropped, falling back to polling", ticket.captcha_id);
+                },
+            }
+        }
+
+        self.poll_until_solved(ticket, initial_delay, poll_interval).await
+    }
+
+    async fn poll_until_solved<C>(&self, ticket: &CaptchaTicket<C>, initial_delay: Duration, mut poll_interval: Duration) -> Result<Solved<C::Answer>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        let max_poll_interval = Duration::from_millis(self.params.max_poll_interval_ms)
+            .max(poll_interval);
+
+        let since_submit = ticket.submitted_at.elapsed()
+            .unwrap_or_default();
+        if since_submit < initial_delay {
+            log::debug!("captcha id = {} submitted recently, sleeping for {:?}", ticket.captcha_id, initial_delay - since_submit);
+            sleep(initial_delay - since_submit).await;
+        }
+
+        // bulk answers carry no user agent, so types that need one are polled individually
+        if self.params.is_bulk_poll && ticket.protocol == Protocol::Legacy && !C::returns_user_agent() {
+            match self.bulk_poller.wait(&ticket.captcha_id, poll_interval).await {
+                Some(code) =>
+                    if let Some(solved) = solved_from_poll_result::<C>(&ticket.captcha_id, ApiResponse::from_code(code))? {
+                        return Ok(solved);
+                    },
+                None =>
+                    log::debug!("bulk poll failed for captcha id = {}, falling back to polling", ticket.captcha_id),
+            }
+        }
+
+        loop {
+            let now = Instant::now();
+            match self.poll(ticket).await? {
+                None => {
+                    let elapsed = now.elapsed();
+                    if elapsed < poll_interval {
+                        sleep(poll_interval - elapsed).await;
+                    }
+                    if self.params.is_adaptive_poll {
+                        poll_interval = (poll_interval * 3 / 2).min(max_poll_interval);
+                    }
+                },
+                Some(solved) =>
+                    return Ok(solved),
+            }
+        }
+    }
+
+    async fn cached_balance<E>(&self) -> Result<Decimal, ApiError<E>> {
+        let balance_cache_ttl = Duration::from_millis(self.params.balance_cache_ttl_ms);
+        let mut balance_cache = self.balance_cache.lock().await;
+        if let Some((fetched_at, balance)) = *balance_cache {
+            if fetched_at.elapsed() < balance_cache_ttl {
+                return Ok(balance);
+            }
+        }
+        let balance = self.params.retry_policy
+            .run(|| async { self.balance().await.map_err(ApiError::Balance) })
+            .await?;
+        *balance_cache = Some((Instant::now(), balance));
+        Ok(balance)
+    }
+
+    pub async fn balance(&self) -> Result<Decimal, BalanceError> {
+        let request_builder = match self.params.protocol {
+            Protocol::Legacy => {
+                log::debug!("making getbalance request to {}", self.params.api_result_url);
+
+                self.client.get(&self.params.api_result_url)
+                    .query(&[
+                        ("key", &*self.api_token.key),
+                        ("action", "getbalance"),
+                        ("json", "1"),
+                    ])
+            },
+            Protocol::JsonV2 => {
+                log::debug!("making getBalance request to {}", self.params.api_get_balance_url);
+
+                self.client.post(&self.params.api_get_balance_url)
+                    .json(&task::GetBalanceRequest { client_key: &self.api_token.key, })
+            },
+        };
+        let response = request_builder.send().await
+            .map_err(BalanceError::SendBalanceRequest)?;
+        let status_code = response.status();
+        if status_code != StatusCode::OK {
+            return Err(BalanceError::SendBalanceRequestBadStatusCode { status_code, });
+        }
+        let api_response_string = response.text().await
+            .map_err(BalanceError::ReadBalanceResponse)?;
+
+        log::debug!("request finished, server responded: {}", api_response_string);
+
+        let amount = match self.params.protocol {
+            Protocol::Legacy =>
+                ApiResponse::parse(&api_response_string)
+                    .map_err(BalanceError::DecodeBalanceResponse)?
+                    .extract_balance(),
+            Protocol::JsonV2 =>
+                task::TaskApiResponse::parse(&api_response_string)
+                    .map_err(BalanceError::DecodeBalanceResponse)?
+                    .extract_balance()
+                    .map_err(BalanceResponseError::TaskResponse),
+        }
+            .map_err(BalanceError::BalanceResponse)?;
+
+        amount.parse()
+            .or_else(|_| Decimal::from_scientific(&amount))
+            .map_err(|error| BalanceError::BalanceResponse(BalanceResponseError::InvalidAmount { source: amount, error, }))
+    }
+
+    pub async fn add_pingback<T>(&self, addr: T) -> Result<(), PingbackError> where T: AsRef<str> {
+        self.pingback_request("add_pingback", Some(addr.as_ref())).await?;
+        Ok(())
+    }
+
+    pub async fn get_pingback(&self) -> Result<Vec<String>, PingbackError> {
+        let request = self.pingback_request("get_pingback", None).await?;
+        match serde_json::from_str(&request) {
+            Ok(addrs) =>
+                Ok(addrs),
+            Err(..) =>
+                Ok(request.split(',').map(str::trim).filter(|addr| !addr.is_empty()).map(ToString::to_string).collect()),
+        }
+    }
+
+    pub async fn del_pingback<T>(&self, addr: T) -> Result<(), PingbackError> where T: AsRef<str> {
+        self.pingback_request("del_pingback", Some(addr.as_ref())).await?;
+        Ok(())
+    }
+
+    pub async fn del_all_pingbacks(&self) -> Result<(), PingbackError> {
+        self.del_pingback("all").await
+    }
+
+    async fn pingback_request(&self, action: &str, maybe_addr: Option<&str>) -> Result<String, PingbackError> {
+        log::debug!("making {} request to {}", action, self.params.api_result_url);
+
+        let mut get_parameters = vec![
+            ("key", &*self.api_token.key),
+            ("action", action),
+            ("json", "1"),
+        ];
+        if let Some(addr) = maybe_addr {
+            get_parameters.push(("addr", addr));
+        }
+        let response = self.client.get(&self.params.api_result_url)
+            .query(&get_parameters)
+            .send()
+            .await
+            .map_err(PingbackError::SendPingbackRequest)?;
+        let status_code = response.status();
+        if status_code != StatusCode::OK {
+            return Err(PingbackError::SendPingbackRequestBadStatusCode { status_code, });
+        }
+        let api_response_string = response.text().await
+            .map_err(PingbackError::ReadPingbackResponse)?;
+
+        log::debug!("request finished, server responded: {}", api_response_string);
+
+        ApiResponse::parse(&api_response_string)
+            .map_err(PingbackError::DecodePingbackResponse)?
+            .extract_pingback_result()
+            .map_err(PingbackError::PingbackResponse)
+    }
+
+    pub async fn report_good(&self, captcha_id: &CaptchaId) -> Result<(), ReportError> {
+        self.report(captcha_id, true).await
+    }
+
+    pub async fn report_bad(&self, captcha_id: &CaptchaId) -> Result<(), ReportError> {
+        self.report(captcha_id, false).await
+    }
+
+    async fn report(&self, captcha_id: &CaptchaId, is_good: bool) -> Result<(), ReportError> {
+        let request_builder = match self.params.protocol {
+            Protocol::Legacy => {
+                let action = if is_good { "reportgood" } else { "reportbad" };
+                log::debug!("making {} request with captcha id = {} to {}", action, captcha_id, self.params.api_result_url);
+
+                self.client.get(&self.params.api_result_url)
+                    .query(&[
+                        ("key", &*self.api_token.key),
+                        ("action", action),
+                        ("id", captcha_id.as_str()),
+                        ("json", "1"),
+                    ])
+            },
+            Protocol::JsonV2 => {
+                let url = if is_good { &self.params.api_report_correct_url } else { &self.params.api_report_incorrect_url };
+                log::debug!("making report request with task id = {} to {}", captcha_id, url);
+
+                self.client.post(url)
+                    .json(&task::GetTaskResultRequest::new(&self.api_token.key, captcha_id.as_str()))
+            },
+        };
+        let response = request_builder.send().await
+            .map_err(ReportError::SendReportRequest)?;
+        let status_code = response.status();
+        if status_code != StatusCode::OK {
+            return Err(ReportError::SendReportRequestBadStatusCode { status_code, });
+        }
+        let api_response_string = response.text().await
+            .map_err(ReportError::ReadReportResponse)?;
+
+        log::debug!("request finished, server responded: {}", api_response_string);
+
+        match self.params.protocol {
+            Protocol::Legacy =>
+                ApiResponse::parse(&api_response_string)
+                    .map_err(ReportError::DecodeReportResponse)?
+                    .extract_report_result(),
+            Protocol::JsonV2 =>
+                task::TaskApiResponse::parse(&api_response_string)
+                    .map_err(ReportError::DecodeReportResponse)?
+                    .extract_success()
+                    .map_err(ReportResponseError::TaskResponse),
+        }
+            .map_err(ReportError::ReportResponse)
+    }
+
+    async fn submit_legacy<C>(&self, captcha: &C) -> Result<String, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
         log::debug!("making request with key = {} to {}", self.api_token.key, self.params.api_request_url);
 
-        let client = Client::new();
-        let request_builder = client.post(&self.params.api_request_url);
-        let request_builder = captcha.prepare_request(&self.api_token, request_builder).await
+        let request_builder = self.client.post(&self.params.api_request_url);
+        let mut request_builder = captcha.prepare_request(&self.api_token, request_builder).await
             .map_err(ApiError::PrepareCaptchaRequest)?;
+        if let Some(pingback_url) = &self.params.maybe_pingback_url {
+            request_builder = request_builder.query(&[("pingback", pingback_url)]);
+        }
         let response = request_builder.send().await
             .map_err(ApiError::SendCaptchaRequest)?;
         let status_code = response.status();
@@ -115,52 +785,102 @@ impl Api {
         let api_response = ApiResponse::parse(&api_response_string)
             .map_err(ApiError::DecodeCaptchaResponse)?;
 
-        let captcha_id = api_response.extract_captcha_id()
-            .map_err(ApiError::CaptchaResponse)?;
+        api_response.extract_captcha_id()
+            .map_err(ApiError::CaptchaResponse)
+    }
+
+    async fn submit_task<C>(&self, captcha: &C) -> Result<String, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        log::debug!("making createTask request with key = {} to {}", self.api_token.key, self.params.api_create_task_url);
+
+        let task = captcha.prepare_task().await
+            .map_err(ApiError::PrepareCaptchaRequest)?;
+        let maybe_language_pool = captcha.language_pool()
+            .map_err(ApiError::PrepareCaptchaRequest)?;
+        let response = self.client.post(&self.params.api_create_task_url)
+            .json(&task::CreateTaskRequest {
+                client_key: &self.api_token.key,
+                task: &task,
+                callback_url: self.params.maybe_pingback_url.as_deref(),
+                language_pool: maybe_language_pool,
+            })
+            .send()
+            .await
+            .map_err(ApiError::SendCaptchaRequest)?;
+        let status_code = response.status();
+        if status_code != StatusCode::OK {
+            return Err(ApiError::SendCaptchaRequestBadStatusCode { status_code, });
+        }
+        let api_response_string = response.text().await
+            .map_err(ApiError::ReadCaptchaResponse)?;
+        let api_response = task::TaskApiResponse::parse(&api_response_string)
+            .map_err(ApiError::DecodeCaptchaResponse)?;
+
+        api_response.extract_task_id()
+            .map_err(ApiError::TaskResponse)
+    }
 
-        log::debug!("request finished, captcha id = {}, sleeping for {} ms", captcha_id, self.params.poll_timeout_ms);
-        sleep(Duration::from_millis(self.params.poll_timeout_ms)).await;
+    async fn poll_legacy<C>(&self, captcha_id: &CaptchaId) -> Result<Option<Solved<C::Answer>>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        log::debug!("making request with captcha id = {} to {}", captcha_id, self.params.api_result_url);
 
         let get_parameters = [
             ("key", &*self.api_token.key),
             ("action", "get"),
-            ("id", &captcha_id),
+            ("id", captcha_id.as_str()),
             ("json", "1"),
         ];
+        let response = self.client.get(&self.params.api_result_url)
+            .query(&get_parameters)
+            .send()
+            .await
+            .map_err(ApiError::SendPollRequest)?;
+        let status_code = response.status();
+        if status_code != StatusCode::OK {
+            return Err(ApiError::SendPollRequestBadStatusCode { status_code, });
+        }
+        let api_response_string = response.text().await
+            .map_err(ApiError::ReadPollResponse)?;
 
-        loop {
-            log::debug!("making request with captcha id = {} to {}", captcha_id, self.params.api_result_url);
+        log::debug!("request finished, server responded: {}", api_response_string);
 
-            let now = Instant::now();
-            let response = client.get(&self.params.api_result_url)
-                .query(&get_parameters)
-                .send()
-                .await
-                .map_err(ApiError::SendPollRequest)?;
-            let status_code = response.status();
-            if status_code != StatusCode::OK {
-                return Err(ApiError::SendPollRequestBadStatusCode { status_code, });
-            }
-            let api_response_string = response.text().await
-                .map_err(ApiError::ReadPollResponse)?;
+        let api_response = ApiResponse::parse(&api_response_string)
+            .map_err(ApiError::DecodePollResponse)?;
 
-            log::debug!("request finished, server responded: {}", api_response_string);
+        solved_from_poll_result::<C>(captcha_id, api_response)
+    }
 
-            let api_response = ApiResponse::parse(&api_response_string)
-                .map_err(ApiError::DecodePollResponse)?;
+    async fn poll_task<C>(&self, captcha_id: &CaptchaId) -> Result<Option<Solved<C::Answer>>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+        log::debug!("making getTaskResult request with task id = {} to {}", captcha_id, self.params.api_get_task_result_url);
 
-            let poll_result = api_response.extract_poll_result()
-                .map_err(ApiError::PollResponse)?;
-            match poll_result {
-                PollResult::NotReady => {
-                    let elapsed = now.elapsed().as_millis() as u64;
-                    if elapsed < self.params.poll_timeout_ms {
-                        sleep(Duration::from_millis(self.params.poll_timeout_ms - elapsed)).await;
-                    }
-                },
-                PollResult::Ready { solved_captcha, } =>
-                    return Ok(Solved { answer: solved_captcha, }),
-            }
+        let response = self.client.post(&self.params.api_get_task_result_url)
+            .json(&task::GetTaskResultRequest::new(&self.api_token.key, captcha_id.as_str()))
+            .send()
+            .await
+            .map_err(ApiError::SendPollRequest)?;
+        let status_code = response.status();
+        if status_code != StatusCode::OK {
+            return Err(ApiError::SendPollRequestBadStatusCode { status_code, });
+        }
+        let api_response_string = response.text().await
+            .map_err(ApiError::ReadPollResponse)?;
+
+        log::debug!("request finished, server responded: {}", api_response_string);
+
+        let api_response = task::TaskApiResponse::parse(&api_response_string)
+            .map_err(ApiError::DecodePollResponse)?;
+
+        let poll_result = api_response.extract_poll_result()
+            .map_err(ApiError::TaskResponse)?;
+        match poll_result {
+            task::TaskPollResult::NotReady =>
+                Ok(None),
+            task::TaskPollResult::Ready { solution, } => {
+                let maybe_user_agent = solution.get("userAgent")
+                    .and_then(serde_json::Value::as_str)
+                    .map(ToString::to_string);
+                let answer = C::parse_solution(solution)
+                    .map_err(ApiError::DecodeAnswer)?;
+                Ok(Some(Solved { captcha_id: captcha_id.clone(), answer, maybe_user_agent, }))
+            },
         }
     }
 }
@@ -168,14 +888,88 @@ impl Api {
 #[async_trait]
 pub trait CaptchaRequest {
     type PrepareRequestError;
+    type Answer;
+    type Task: serde::Serialize + Send;
+    /// Stable name stored in a `CaptchaTicket` to check it is resumed as the same captcha type.
+    const CAPTCHA_TYPE: &'static str;
 
     async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError>;
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError>;
+
+    fn language_pool(&self) -> Result<Option<&'static str>, Self::PrepareRequestError> {
+        Ok(None)
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError>;
+
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError>;
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(TOKEN_INITIAL_DELAY_MS)
+    }
+
+    fn recommended_poll_interval() -> Duration {
+        Duration::from_millis(TOKEN_POLL_INTERVAL_MS)
+    }
+
+    fn returns_user_agent() -> bool {
+        false
+    }
 }
 
-#[derive(Deserialize, Debug)]
+enum Interruption {
+    Timeout,
+    Cancelled,
+}
+
+async fn interruption(maybe_deadline: Option<Instant>, maybe_cancellation_token: Option<&CancellationToken>) -> Interruption {
+    let deadline = async {
+        match maybe_deadline {
+            Some(deadline) =>
+                sleep_until(deadline.into()).await,
+            None =>
+                pending().await,
+        }
+    };
+    let cancelled = async {
+        match maybe_cancellation_token {
+            Some(cancellation_token) =>
+                cancellation_token.cancelled().await,
+            None =>
+                pending().await,
+        }
+    };
+
+    tokio::select! {
+        () = deadline =>
+            Interruption::Timeout,
+        () = cancelled =>
+            Interruption::Cancelled,
+    }
+}
+
+fn solved_from_poll_result<C>(captcha_id: &CaptchaId, api_response: ApiResponse) -> Result<Option<Solved<C::Answer>>, ApiError<C::PrepareRequestError>> where C: CaptchaRequest {
+    let poll_result = api_response.extract_poll_result()
+        .map_err(ApiError::PollResponse)?;
+    match poll_result {
+        PollResult::NotReady =>
+            Ok(None),
+        PollResult::Ready { solved_captcha, maybe_user_agent, } => {
+            let answer = C::parse_answer(solved_captcha)
+                .map_err(ApiError::DecodeAnswer)?;
+            Ok(Some(Solved { captcha_id: captcha_id.clone(), answer, maybe_user_agent, }))
+        },
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
 pub struct ApiResponse {
     pub status: i32,
+    #[serde(deserialize_with = "deserialize_request")]
     pub request: String,
+    #[serde(default)]
+    pub useragent: Option<String>,
 }
 
 #[derive(Debug)]
@@ -224,7 +1018,7 @@ pub enum PollResponseError {
 
 enum PollResult {
     NotReady,
-    Ready { solved_captcha: String, },
+    Ready { solved_captcha: String, maybe_user_agent: Option<String>, },
 }
 
 #[derive(Debug)]
@@ -233,13 +1027,47 @@ pub enum DecodeApiResponse {
     UnexpectedResponse { source: String, error: serde_json::Error, },
 }
 
+#[derive(Debug)]
+pub enum DecodeAnswerError {
+    InvalidJson { source: String, error: serde_json::Error, },
+    InvalidFormat { source: String, },
+}
+
+impl DecodeAnswerError {
+    pub fn from_json<T>(answer: String) -> Result<T, DecodeAnswerError> where T: DeserializeOwned {
+        serde_json::from_str(&answer)
+            .map_err(|error| DecodeAnswerError::InvalidJson { source: answer, error, })
+    }
+
+    pub fn from_solution<T>(solution: serde_json::Value) -> Result<T, DecodeAnswerError> where T: DeserializeOwned {
+        serde_json::from_value(solution.clone())
+            .map_err(|error| DecodeAnswerError::InvalidJson { source: solution.to_string(), error, })
+    }
+
+    pub fn solution_field(solution: &serde_json::Value, field: &str) -> Result<String, DecodeAnswerError> {
+        solution.get(field)
+            .and_then(serde_json::Value::as_str)
+            .map(ToString::to_string)
+            .ok_or_else(|| DecodeAnswerError::InvalidFormat { source: solution.to_string(), })
+    }
+}
+
+fn deserialize_request<'de, D>(deserializer: D) -> Result<String, D::Error> where D: Deserializer<'de> {
+    match <serde_json::Value as serde::Deserialize>::deserialize(deserializer)? {
+        serde_json::Value::String(request) =>
+            Ok(request),
+        other =>
+            Ok(other.to_string()),
+    }
+}
+
 impl ApiResponse {
     fn parse(api_response_str: &str) -> Result<ApiResponse, DecodeApiResponse> {
         match api_response_str {
             "IP_BANNED" =>
                 Err(DecodeApiResponse::IpBanned),
             _ => {
-                serde_json::from_str(&api_response_str)
+                serde_json::from_str(api_response_str)
                     .map_err(|error| DecodeApiResponse::UnexpectedResponse {
                         source: api_response_str.to_string(),
                         error,
@@ -250,89 +1078,575 @@ impl ApiResponse {
 
     fn extract_captcha_id(self) -> Result<String, CaptchaResponseError> {
         match self {
-            ApiResponse { status: 1, request, } =>
+            ApiResponse { status: 1, request, .. } =>
                 Ok(request),
-            ApiResponse { status: 0, request, } if request == "ERROR_WRONG_USER_KEY" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_WRONG_USER_KEY" =>
                 Err(CaptchaResponseError::WrongUserKey),
-            ApiResponse { status: 0, request, } if request == "ERROR_KEY_DOES_NOT_EXIST" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_KEY_DOES_NOT_EXIST" =>
                 Err(CaptchaResponseError::KeyDoesNotExist),
-            ApiResponse { status: 0, request, } if request == "ERROR_ZERO_BALANCE" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_ZERO_BALANCE" =>
                 Err(CaptchaResponseError::ZeroBalance),
-            ApiResponse { status: 0, request, } if request == "ERROR_PAGEURL" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_PAGEURL" =>
                 Err(CaptchaResponseError::Pageurl),
-            ApiResponse { status: 0, request, } if request == "ERROR_NO_SLOT_AVAILABLE" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_NO_SLOT_AVAILABLE" =>
                 Err(CaptchaResponseError::NoSlotAvailable),
-            ApiResponse { status: 0, request, } if request == "ERROR_ZERO_CAPTCHA_FILESIZE" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_ZERO_CAPTCHA_FILESIZE" =>
                 Err(CaptchaResponseError::ZeroCaptchaFilesize),
-            ApiResponse { status: 0, request, } if request == "ERROR_TOO_BIG_CAPTCHA_FILESIZE" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_TOO_BIG_CAPTCHA_FILESIZE" =>
                 Err(CaptchaResponseError::TooBigCaptchaFilesize),
-            ApiResponse { status: 0, request, } if request == "ERROR_WRONG_FILE_EXTENSION" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_WRONG_FILE_EXTENSION" =>
                 Err(CaptchaResponseError::WrongFileExtension),
-            ApiResponse { status: 0, request, } if request == "ERROR_IMAGE_TYPE_NOT_SUPPORTED" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_IMAGE_TYPE_NOT_SUPPORTED" =>
                 Err(CaptchaResponseError::ImageTypeNotSupported),
-            ApiResponse { status: 0, request, } if request == "ERROR_UPLOAD" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_UPLOAD" =>
                 Err(CaptchaResponseError::Upload),
-            ApiResponse { status: 0, request, } if request == "ERROR_IP_NOT_ALLOWED" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_IP_NOT_ALLOWED" =>
                 Err(CaptchaResponseError::IpNotAllowed),
-            ApiResponse { status: 0, request, } if request == "IP_BANNED" =>
+            ApiResponse { status: 0, request, .. } if request == "IP_BANNED" =>
                 Err(CaptchaResponseError::IpBanned),
-            ApiResponse { status: 0, request, } if request == "ERROR_BAD_TOKEN_OR_PAGEURL" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_BAD_TOKEN_OR_PAGEURL" =>
                 Err(CaptchaResponseError::BadTokenOrPageurl),
-            ApiResponse { status: 0, request, } if request == "ERROR_GOOGLEKEY" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_GOOGLEKEY" =>
                 Err(CaptchaResponseError::Googlekey),
-            ApiResponse { status: 0, request, } if request == "ERROR_WRONG_GOOGLEKEY" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_WRONG_GOOGLEKEY" =>
                 Err(CaptchaResponseError::WrongGooglekey),
-            ApiResponse { status: 0, request, } if request == "ERROR_CAPTCHAIMAGE_BLOCKED" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_CAPTCHAIMAGE_BLOCKED" =>
                 Err(CaptchaResponseError::CaptchaimageBlocked),
-            ApiResponse { status: 0, request, } if request == "TOO_MANY_BAD_IMAGES" =>
+            ApiResponse { status: 0, request, .. } if request == "TOO_MANY_BAD_IMAGES" =>
                 Err(CaptchaResponseError::TooManyBadImages),
-            ApiResponse { status: 0, request, } if request == "MAX_USER_TURN" =>
+            ApiResponse { status: 0, request, .. } if request == "MAX_USER_TURN" =>
                 Err(CaptchaResponseError::MaxUserTurn),
-            ApiResponse { status: 0, request, } if request == "ERROR_BAD_PARAMETERS" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_BAD_PARAMETERS" =>
                 Err(CaptchaResponseError::BadParameters),
-            ApiResponse { status: 0, request, } if request == "ERROR_BAD_PROXY" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_BAD_PROXY" =>
                 Err(CaptchaResponseError::BadProxy),
             other =>
                 Err(CaptchaResponseError::UnexpectedApiResponse(other)),
         }
     }
 
+    fn extract_balance(self) -> Result<String, BalanceResponseError> {
+        match self {
+            ApiResponse { status: 1, request, .. } =>
+                Ok(request),
+            other => {
+                let request = other.request.clone();
+                match other.extract_poll_result() {
+                    Err(error) =>
+                        Err(BalanceResponseError::PollResponse(error)),
+                    Ok(..) =>
+                        Err(BalanceResponseError::UnexpectedResponse { request, }),
+                }
+            },
+        }
+    }
+
+    fn from_code(code: String) -> ApiResponse {
+        let status = if code.starts_with("ERROR") || code == "IP_BANNED" || code == "CAPCHA_NOT_READY" { 0 } else { 1 };
+        ApiResponse { status, request: code, useragent: None, }
+    }
+
+    fn extract_pingback_result(self) -> Result<String, PollResponseError> {
+        match self {
+            ApiResponse { status: 1, request, .. } =>
+                Ok(request),
+            ApiResponse { status: 0, ref request, .. } if request == "CAPCHA_NOT_READY" =>
+                Err(PollResponseError::UnexpectedApiResponse(self)),
+            other =>
+                match other.clone().extract_poll_result() {
+                    Err(error) =>
+                        Err(error),
+                    Ok(..) =>
+                        Err(PollResponseError::UnexpectedApiResponse(other)),
+                },
+        }
+    }
+
+    fn extract_report_result(self) -> Result<(), ReportResponseError> {
+        match self {
+            ApiResponse { status: 1, .. } =>
+                Ok(()),
+            ApiResponse { status: 0, request, .. } if request == "ERROR_REPORT_NOT_RECORDED" =>
+                Err(ReportResponseError::ReportNotRecorded),
+            ApiResponse { status: 0, request, .. } if request == "ERROR_DUPLICATE_REPORT" =>
+                Err(ReportResponseError::DuplicateReport),
+            other => {
+                let request = other.request.clone();
+                match other.extract_poll_result() {
+                    Err(error) =>
+                        Err(ReportResponseError::PollResponse(error)),
+                    Ok(..) =>
+                        Err(ReportResponseError::UnexpectedResponse { request, }),
+                }
+            },
+        }
+    }
+
     fn extract_poll_result(self) -> Result<PollResult, PollResponseError> {
         match self {
-            ApiResponse { status: 1, request, } =>
-                Ok(PollResult::Ready { solved_captcha: request, }),
-            ApiResponse { status: 0, request, } if request == "CAPCHA_NOT_READY" =>
+            ApiResponse { status: 1, request, useragent: maybe_user_agent, } =>
+                Ok(PollResult::Ready { solved_captcha: request, maybe_user_agent, }),
+            ApiResponse { status: 0, request, .. } if request == "CAPCHA_NOT_READY" =>
                 Ok(PollResult::NotReady),
-            ApiResponse { status: 0, request, } if request == "ERROR_CAPTCHA_UNSOLVABLE" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_CAPTCHA_UNSOLVABLE" =>
                 Err(PollResponseError::ErrorCaptchaUnsolvable),
-            ApiResponse { status: 0, request, } if request == "ERROR_WRONG_USER_KEY" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_WRONG_USER_KEY" =>
                 Err(PollResponseError::ErrorWrongUserKey),
-            ApiResponse { status: 0, request, } if request == "ERROR_KEY_DOES_NOT_EXIST" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_KEY_DOES_NOT_EXIST" =>
                 Err(PollResponseError::ErrorKeyDoesNotExist),
-            ApiResponse { status: 0, request, } if request == "ERROR_WRONG_ID_FORMAT" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_WRONG_ID_FORMAT" =>
                 Err(PollResponseError::ErrorWrongIdFormat),
-            ApiResponse { status: 0, request, } if request == "ERROR_WRONG_CAPTCHA_ID" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_WRONG_CAPTCHA_ID" =>
                 Err(PollResponseError::ErrorWrongCaptchaId),
-            ApiResponse { status: 0, request, } if request == "ERROR_BAD_DUPLICATES" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_BAD_DUPLICATES" =>
                 Err(PollResponseError::ErrorBadDuplicates),
-            ApiResponse { status: 0, request, } if request == "ERROR_REPORT_NOT_RECORDED" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_REPORT_NOT_RECORDED" =>
                 Err(PollResponseError::ErrorReportNotRecorded),
-            ApiResponse { status: 0, request, } if request == "ERROR_DUPLICATE_REPORT" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_DUPLICATE_REPORT" =>
                 Err(PollResponseError::ErrorDuplicateReport),
-            ApiResponse { status: 0, request, } if request.starts_with("ERROR:") =>
+            ApiResponse { status: 0, request, .. } if request.starts_with("ERROR:") =>
                 Err(PollResponseError::RequestLimitExceeded { code: request[6 ..].trim().to_string(), }),
-            ApiResponse { status: 0, request, } if request == "ERROR_IP_ADDRES" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_IP_ADDRES" =>
                 Err(PollResponseError::ErrorIpAddres),
-            ApiResponse { status: 0, request, } if request == "IP_BANNED" =>
+            ApiResponse { status: 0, request, .. } if request == "IP_BANNED" =>
                 Err(PollResponseError::IpBanned),
-            ApiResponse { status: 0, request, } if request == "ERROR_TOKEN_EXPIRED" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_TOKEN_EXPIRED" =>
                 Err(PollResponseError::ErrorTokenExpired),
-            ApiResponse { status: 0, request, } if request == "ERROR_EMPTY_ACTION" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_EMPTY_ACTION" =>
                 Err(PollResponseError::ErrorEmptyAction),
-            ApiResponse { status: 0, request, } if request == "ERROR_PROXY_CONNECTION_FAILED" =>
+            ApiResponse { status: 0, request, .. } if request == "ERROR_PROXY_CONNECTION_FAILED" =>
                 Err(PollResponseError::ErrorProxyConnectionFailed),
             other =>
                 Err(PollResponseError::UnexpectedApiResponse(other)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        Mutex,
+    };
+
+    use tokio::{
+        io::{
+            AsyncReadExt,
+            AsyncWriteExt,
+        },
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Minimal stand-in for `in.php`/`res.php`: records every request as `target body`
+    /// and answers it with whatever `respond` returns.
+    pub(crate) async fn stand_in<F>(respond: F) -> (String, Arc<Mutex<Vec<String>>>) where F: Fn(&str) -> String + Send + Sync + 'static {
+        stand_in_delayed(move |request| (Duration::ZERO, respond(request))).await
+    }
+
+    /// Like `stand_in`, but each answer is sent after the returned delay.
+    async fn stand_in_delayed<F>(respond: F) -> (String, Arc<Mutex<Vec<String>>>) where F: Fn(&str) -> (Duration, String) + Send + Sync + 'static {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let respond = Arc::new(respond);
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let respond = respond.clone();
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let mut buffer = Vec::new();
+                    let head_end = loop {
+                        if let Some(offset) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                            break offset + 4;
+                        }
+                        let mut chunk = [0; 4096];
+                        let bytes_read = stream.read(&mut chunk).await.unwrap();
+                        buffer.extend_from_slice(&chunk[.. bytes_read]);
+                    };
+                    let head = String::from_utf8_lossy(&buffer[.. head_end]).to_string();
+                    let content_length = head.lines()
+                        .filter_map(|line| line.split_once(':'))
+                        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                        .map(|(_, value)| value.trim().parse().unwrap())
+                        .unwrap_or(0);
+                    while buffer.len() < head_end + content_length {
+                        let mut chunk = [0; 4096];
+                        let bytes_read = stream.read(&mut chunk).await.unwrap();
+                        buffer.extend_from_slice(&chunk[.. bytes_read]);
+                    }
+                    let target = head.split(' ').nth(1).unwrap_or_default();
+                    let request = format!("{} {}", target, String::from_utf8_lossy(&buffer[head_end ..]));
+                    let (delay, body) = respond(&request);
+                    recorded.lock().unwrap().push(request);
+                    tokio::time::sleep(delay).await;
+                    let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+        (base_url, requests)
+    }
+
+    fn stand_in_params(base_url: &str) -> Params {
+        Params {
+            api_request_url: format!("{}/in.php", base_url),
+            api_result_url: format!("{}/res.php", base_url),
+            retry_policy: retry::RetryPolicy::no_retry(),
+            ..Params::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn manage_pingback_addresses() {
+        let (base_url, requests) = stand_in(|request| if request.contains("action=get_pingback") {
+            r#"{"status":1,"request":["http://a.example/cb","http://b.example/cb"]}"#.to_string()
+        } else {
+            r#"{"status":1,"request":"OK"}"#.to_string()
+        }).await;
+        let api = Api::new(ApiToken::from("key".to_string()), stand_in_params(&base_url));
+
+        api.add_pingback("http://a.example/cb").await.unwrap();
+        assert_eq!(api.get_pingback().await.unwrap(), vec!["http://a.example/cb", "http://b.example/cb"]);
+        api.del_pingback("http://a.example/cb").await.unwrap();
+        api.del_all_pingbacks().await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[0].starts_with("/res.php?key=key&action=add_pingback&json=1&addr=http%3A%2F%2Fa.example%2Fcb"), "{}", requests[0]);
+        assert!(requests[1].starts_with("/res.php?key=key&action=get_pingback&json=1 "), "{}", requests[1]);
+        assert!(requests[2].starts_with("/res.php?key=key&action=del_pingback&json=1&addr=http%3A%2F%2Fa.example%2Fcb"), "{}", requests[2]);
+        assert!(requests[3].starts_with("/res.php?key=key&action=del_pingback&json=1&addr=all"), "{}", requests[3]);
+    }
+
+    #[tokio::test]
+    async fn manage_pingback_error() {
+        let (base_url, _) = stand_in(|_| r#"{"status":0,"request":"ERROR_WRONG_USER_KEY"}"#.to_string()).await;
+        let api = Api::new(ApiToken::from("key".to_string()), stand_in_params(&base_url));
+
+        assert!(matches!(
+            api.add_pingback("http://a.example/cb").await,
+            Err(PingbackError::PingbackResponse(PollResponseError::ErrorWrongUserKey))
+        ));
+    }
+
+    #[tokio::test]
+    async fn pingback_wait_falls_back_to_polling() {
+        let (base_url, _) = stand_in(|request| if request.starts_with("/in.php") {
+            r#"{"status":1,"request":"4242"}"#.to_string()
+        } else {
+            r#"{"status":1,"request":"answer"}"#.to_string()
+        }).await;
+        let pingback_listener = pingback::PingbackListener::bind("127.0.0.1:0").await.unwrap();
+        let params = Params {
+            maybe_initial_delay_ms: Some(0),
+            maybe_poll_interval_ms: Some(10),
+            pingback_fallback_poll_interval_ms: 10,
+            maybe_pingback_url: Some(format!("http://{}/", pingback_listener.local_addr())),
+            ..stand_in_params(&base_url)
+        };
+        let api = Api::new(ApiToken::from("key".to_string()), params)
+            .set_pingback_listener(pingback_listener);
+        let captcha = text::CaptchaBuilder::new()
+            .set_question("2 + 2?")
+            .finish()
+            .unwrap();
+
+        let solved = tokio::time::timeout(Duration::from_secs(5), api.solve(&captcha)).await
+            .expect("pingback wait must fall back to polling")
+            .unwrap();
+        assert_eq!(solved.captcha_id, CaptchaId::from("4242".to_string()));
+        assert_eq!(solved.answer, "answer");
+    }
+
+    fn text_captcha() -> text::Captcha {
+        text::CaptchaBuilder::new()
+            .set_question("2 + 2?")
+            .finish()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn solve_timeout_covers_balance_check() {
+        let (base_url, requests) = stand_in_delayed(|_| (Duration::from_secs(10), r#"{"status":1,"request":"10"}"#.to_string())).await;
+        let params = Params {
+            maybe_min_balance: Some(Decimal::new(1, 0)),
+            ..stand_in_params(&base_url)
+        };
+        let api = Api::new(ApiToken::from("key".to_string()), params);
+        let options = SolveOptions::new()
+            .set_timeout(Duration::from_millis(100));
+
+        assert!(matches!(api.solve_with_options(&text_captcha(), &options).await, Err(ApiError::SubmitTimeout)));
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1, "{:?}", requests);
+        assert!(requests[0].contains("action=getbalance"), "{}", requests[0]);
+    }
+
+    #[tokio::test]
+    async fn solve_timeout_covers_retry_backoff() {
+        let (base_url, requests) = stand_in(|_| r#"{"status":0,"request":"ERROR_NO_SLOT_AVAILABLE"}"#.to_string()).await;
+        let params = Params {
+            retry_policy: retry::RetryPolicy {
+                initial_delay_ms: 10000,
+                is_jitter: false,
+                ..retry::RetryPolicy::default()
+            },
+            ..stand_in_params(&base_url)
+        };
+        let api = Api::new(ApiToken::from("key".to_string()), params);
+        let options = SolveOptions::new()
+            .set_timeout(Duration::from_millis(100));
+
+        assert!(matches!(api.solve_with_options(&text_captcha(), &options).await, Err(ApiError::SubmitTimeout)));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn solve_cancelled_before_submit() {
+        let (base_url, requests) = stand_in(|_| r#"{"status":1,"request":"4242"}"#.to_string()).await;
+        let api = Api::new(ApiToken::from("key".to_string()), stand_in_params(&base_url));
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+        let options = SolveOptions::new()
+            .set_cancellation_token(cancellation_token);
+
+        assert!(matches!(api.solve_with_options(&text_captcha(), &options).await, Err(ApiError::SubmitCancelled)));
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn solve_timeout_during_submit_returns_captcha_id() {
+        let (base_url, requests) = stand_in_delayed(|request| if request.starts_with("/in.php") {
+            (Duration::from_millis(300), r#"{"status":1,"request":"4242"}"#.to_string())
+        } else {
+            (Duration::ZERO, r#"{"status":0,"request":"CAPCHA_NOT_READY"}"#.to_string())
+        }).await;
+        let api = Api::new(ApiToken::from("key".to_string()), stand_in_params(&base_url));
+        let options = SolveOptions::new()
+            .set_timeout(Duration::from_millis(100));
+
+        match api.solve_with_options(&text_captcha(), &options).await {
+            Err(ApiError::Timeout { captcha_id, }) =>
+                assert_eq!(captcha_id, CaptchaId::from("4242".to_string())),
+            other =>
+                panic!("unexpected result: {:?}", other.map(|solved| solved.answer)),
+        }
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1, "{:?}", requests);
+        assert!(requests[0].starts_with("/in.php"), "{}", requests[0]);
+    }
+
+    #[tokio::test]
+    async fn solve_cancelled_during_submit_returns_captcha_id() {
+        let (base_url, _) = stand_in_delayed(|_| (Duration::from_millis(300), r#"{"status":1,"request":"4242"}"#.to_string())).await;
+        let api = Api::new(ApiToken::from("key".to_string()), stand_in_params(&base_url));
+        let cancellation_token = CancellationToken::new();
+        let options = SolveOptions::new()
+            .set_cancellation_token(cancellation_token.clone());
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancellation_token.cancel();
+        });
+
+        match api.solve_with_options(&text_captcha(), &options).await {
+            Err(ApiError::Cancelled { captcha_id, }) =>
+                assert_eq!(captcha_id, CaptchaId::from("4242".to_string())),
+            other =>
+                panic!("unexpected result: {:?}", other.map(|solved| solved.answer)),
+        }
+    }
+
+    #[tokio::test]
+    async fn solve_timeout_unregisters_pingback() {
+        let (base_url, _) = stand_in(|request| if request.starts_with("/in.php") {
+            r#"{"status":1,"request":"4242"}"#.to_string()
+        } else {
+            r#"{"status":0,"request":"CAPCHA_NOT_READY"}"#.to_string()
+        }).await;
+        let pingback_listener = pingback::PingbackListener::bind("127.0.0.1:0").await.unwrap();
+        let pingback_url = format!("http://{}/", pingback_listener.local_addr());
+        let params = Params {
+            maybe_initial_delay_ms: Some(0),
+            maybe_poll_interval_ms: Some(10),
+            maybe_pingback_url: Some(pingback_url.clone()),
+            ..stand_in_params(&base_url)
+        };
+        let api = Api::new(ApiToken::from("key".to_string()), params)
+            .set_pingback_listener(pingback_listener);
+        let options = SolveOptions::new()
+            .set_timeout(Duration::from_millis(100));
+
+        match api.solve_with_options(&text_captcha(), &options).await {
+            Err(ApiError::Timeout { captcha_id, }) =>
+                assert_eq!(captcha_id, CaptchaId::from("4242".to_string())),
+            other =>
+                panic!("unexpected result: {:?}", other.map(|solved| solved.answer)),
+        }
+
+        let response = Client::new().post(&pingback_url)
+            .body("id=4242&code=late")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn resumed_ticket_must_match_captcha_type() {
+        let (base_url, requests) = stand_in(|_| r#"{"status":1,"request":"4242"}"#.to_string()).await;
+        let api = Api::new(ApiToken::from("key".to_string()), stand_in_params(&base_url));
+        let ticket = api.submit(&text_captcha()).await.unwrap();
+        assert_eq!(ticket.captcha_type(), "text");
+
+        let stored = serde_json::to_string(&ticket).unwrap();
+        let resumed: CaptchaTicket<grid::Captcha> = serde_json::from_str(&stored).unwrap();
+        match api.poll(&resumed).await {
+            Err(ApiError::CaptchaTypeMismatch { expected, found, }) => {
+                assert_eq!(expected, "grid");
+                assert_eq!(found, "text");
+            },
+            other =>
+                panic!("unexpected result: {:?}", other.map(|maybe_solved| maybe_solved.map(|solved| solved.answer))),
+        }
+        assert!(matches!(api.wait(&resumed).await, Err(ApiError::CaptchaTypeMismatch { .. })));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn report_good_and_bad() {
+        let (base_url, requests) = stand_in(|request| if request.contains("id=1&") {
+            r#"{"status":1,"request":"OK_REPORT_RECORDED"}"#.to_string()
+        } else if request.contains("id=2&") {
+            r#"{"status":0,"request":"ERROR_REPORT_NOT_RECORDED"}"#.to_string()
+        } else {
+            r#"{"status":0,"request":"ERROR_DUPLICATE_REPORT"}"#.to_string()
+        }).await;
+        let api = Api::new(ApiToken::from("key".to_string()), stand_in_params(&base_url));
+
+        api.report_good(&CaptchaId::from("1".to_string())).await.unwrap();
+        assert!(matches!(
+            api.report_bad(&CaptchaId::from("2".to_string())).await,
+            Err(ReportError::ReportResponse(ReportResponseError::ReportNotRecorded))
+        ));
+        assert!(matches!(
+            api.report_bad(&CaptchaId::from("3".to_string())).await,
+            Err(ReportError::ReportResponse(ReportResponseError::DuplicateReport))
+        ));
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("/res.php?key=key&action=reportgood&id=1&json=1 "), "{}", requests[0]);
+        assert!(requests[1].starts_with("/res.php?key=key&action=reportbad&id=2&json=1 "), "{}", requests[1]);
+        assert!(requests[2].starts_with("/res.php?key=key&action=reportbad&id=3&json=1 "), "{}", requests[2]);
+    }
+
+    #[tokio::test]
+    async fn low_balance_blocks_submit_and_is_cached() {
+        let (base_url, requests) = stand_in(|request| if request.contains("action=getbalance") {
+            r#"{"status":1,"request":"0.25"}"#.to_string()
+        } else {
+            r#"{"status":1,"request":"4242"}"#.to_string()
+        }).await;
+        let params = Params {
+            maybe_min_balance: Some(Decimal::new(1, 0)),
+            ..stand_in_params(&base_url)
+        };
+        let api = Api::new(ApiToken::from("key".to_string()), params);
+
+        for _ in 0 .. 2 {
+            match api.submit(&text_captcha()).await {
+                Err(ApiError::LowBalance { balance, min_balance, }) => {
+                    assert_eq!(balance, Decimal::new(25, 2));
+                    assert_eq!(min_balance, Decimal::new(1, 0));
+                },
+                other =>
+                    panic!("unexpected result: {:?}", other.map(|ticket| ticket.captcha_id)),
+            }
+        }
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1, "{:?}", requests);
+        assert!(requests[0].starts_with("/res.php?key=key&action=getbalance&json=1 "), "{}", requests[0]);
+    }
+
+    #[tokio::test]
+    async fn balance_parses_legacy_and_v2_amounts() {
+        let (base_url, _) = stand_in(|request| if request.starts_with("/getBalance") {
+            r#"{"errorId":0,"balance":1e-5}"#.to_string()
+        } else {
+            r#"{"status":1,"request":"12.3456"}"#.to_string()
+        }).await;
+        let api = Api::new(ApiToken::from("key".to_string()), stand_in_params(&base_url));
+        assert_eq!(api.balance().await.unwrap(), Decimal::new(123456, 4));
+
+        let params = Params {
+            protocol: Protocol::JsonV2,
+            api_get_balance_url: format!("{}/getBalance", base_url),
+            ..stand_in_params(&base_url)
+        };
+        let api = Api::new(ApiToken::from("key".to_string()), params);
+        assert_eq!(api.balance().await.unwrap(), Decimal::new(1, 5));
+    }
+
+    fn fast_retry_params(base_url: &str) -> Params {
+        Params {
+            maybe_initial_delay_ms: Some(0),
+            maybe_poll_interval_ms: Some(10),
+            retry_policy: retry::RetryPolicy {
+                initial_delay_ms: 10,
+                max_delay_ms: 20,
+                ..retry::RetryPolicy::default()
+            },
+            ..stand_in_params(base_url)
+        }
+    }
+
+    #[tokio::test]
+    async fn transient_poll_errors_do_not_resubmit() {
+        let polls = Arc::new(Mutex::new(0));
+        let responder_polls = polls.clone();
+        let (base_url, requests) = stand_in(move |request| if request.starts_with("/in.php") {
+            r#"{"status":1,"request":"4242"}"#.to_string()
+        } else {
+            let mut polls = responder_polls.lock().unwrap();
+            *polls += 1;
+            if *polls < 3 {
+                r#"{"status":0,"request":"ERROR: 1001"}"#.to_string()
+            } else {
+                r#"{"status":1,"request":"answer"}"#.to_string()
+            }
+        }).await;
+        let api = Api::new(ApiToken::from("key".to_string()), fast_retry_params(&base_url));
+
+        let solved = api.solve(&text_captcha()).await.unwrap();
+        assert_eq!(solved.answer, "answer");
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.iter().filter(|request| request.starts_with("/in.php")).count(), 1, "{:?}", requests);
+        assert_eq!(requests.iter().filter(|request| request.starts_with("/res.php")).count(), 3, "{:?}", requests);
+    }
+
+    #[tokio::test]
+    async fn submit_dropped_after_send_is_not_retried() {
+        // reads the submit request and hangs up without answering, as if the server accepted it and the response was lost
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let submits = Arc::new(Mutex::new(0));
+        let accepted_submits = submits.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buffer = [0; 4096];
+                let _ = stream.read(&mut buffer).await;
+                *accepted_submits.lock().unwrap() += 1;
+            }
+        });
+        let api = Api::new(ApiToken::from("key".to_string()), fast_retry_params(&base_url));
+
+        assert!(matches!(api.submit(&text_captcha()).await, Err(ApiError::SendCaptchaRequest(..))));
+        assert_eq!(*submits.lock().unwrap(), 1);
+    }
+}
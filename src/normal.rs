@@ -3,6 +3,9 @@ use std::{
         Path,
         PathBuf,
     },
+    time::{
+        Duration,
+    },
 };
 
 use tokio::{
@@ -16,6 +19,10 @@ use tokio_util::{
     },
 };
 
+use serde_derive::{
+    Serialize,
+};
+
 use reqwest::{
     Body,
     RequestBuilder,
@@ -29,73 +36,232 @@ use async_trait::{
 use crate::{
     ApiToken,
     CaptchaRequest,
+    DecodeAnswerError,
+    IMAGE_INITIAL_DELAY_MS,
+    IMAGE_POLL_INTERVAL_MS,
+    task,
 };
 
+pub const MAX_LEN_LIMIT: u8 = 20;
+
 pub struct Captcha {
     captcha_data: CaptchaData,
     is_case_sensitive: bool,
+    options: Options,
 }
 
 pub struct CaptchaBuilder {
-    maybe_captcha_data: Option<CaptchaData>,
+    image: ImageSource,
     is_case_sensitive: bool,
+    options: Options,
 }
 
-enum CaptchaData {
+#[derive(Default)]
+struct Options {
+    is_phrase: bool,
+    maybe_numeric: Option<Numeric>,
+    is_calc: bool,
+    maybe_min_len: Option<u8>,
+    maybe_max_len: Option<u8>,
+    maybe_language: Option<Language>,
+    maybe_lang: Option<String>,
+    maybe_text_instructions: Option<String>,
+    maybe_image_instructions: Option<CaptchaData>,
+}
+
+pub(crate) enum CaptchaData {
     UploadFile(PathBuf),
     Base64(String),
 }
 
+#[derive(Default)]
+pub(crate) struct ImageSource {
+    pub(crate) maybe_captcha_data: Option<CaptchaData>,
+    pub(crate) maybe_text_instructions: Option<String>,
+    pub(crate) maybe_image_instructions: Option<CaptchaData>,
+}
+
+macro_rules! impl_image_setters {
+    ($builder:ty) => {
+        impl $builder {
+            pub fn set_upload_file<P>(mut self, path: P) -> Self where P: AsRef<std::path::Path> {
+                self.image.maybe_captcha_data = Some(crate::normal::CaptchaData::upload_file(path));
+                self
+            }
+
+            pub fn set_image_data_base64<T>(mut self, base64_str: T) -> Result<Self, BuilderError> where T: AsRef<str> {
+                let captcha_data = crate::normal::CaptchaData::base64(base64_str.as_ref())
+                    .map_err(|error| BuilderError::InvalidBase64 { source: base64_str.as_ref().to_string(), error, })?;
+                self.image.maybe_captcha_data = Some(captcha_data);
+                Ok(self)
+            }
+
+            pub fn set_image_data_encode_as_base64<T>(mut self, image_data: T) -> Self where T: AsRef<[u8]> {
+                self.image.maybe_captcha_data = Some(crate::normal::CaptchaData::encode_as_base64(image_data));
+                self
+            }
+
+            pub fn set_text_instructions<T>(mut self, text_instructions: T) -> Self where T: AsRef<str> {
+                self.image.maybe_text_instructions = Some(text_instructions.as_ref().to_string());
+                self
+            }
+
+            pub fn set_instructions_upload_file<P>(mut self, path: P) -> Self where P: AsRef<std::path::Path> {
+                self.image.maybe_image_instructions = Some(crate::normal::CaptchaData::upload_file(path));
+                self
+            }
+
+            pub fn set_instructions_image_data_base64<T>(mut self, base64_str: T) -> Result<Self, BuilderError> where T: AsRef<str> {
+                let image_instructions = crate::normal::CaptchaData::base64(base64_str.as_ref())
+                    .map_err(|error| BuilderError::InvalidBase64 { source: base64_str.as_ref().to_string(), error, })?;
+                self.image.maybe_image_instructions = Some(image_instructions);
+                Ok(self)
+            }
+
+            pub fn set_instructions_image_data_encode_as_base64<T>(mut self, image_data: T) -> Self where T: AsRef<[u8]> {
+                self.image.maybe_image_instructions = Some(crate::normal::CaptchaData::encode_as_base64(image_data));
+                self
+            }
+        }
+    };
+}
+
+pub(crate) use impl_image_setters;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Numeric {
+    NotSpecified,
+    NumbersOnly,
+    LettersOnly,
+    NumbersOrLetters,
+    NumbersAndLetters,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language {
+    NotSpecified,
+    Cyrillic,
+    Latin,
+}
+
 #[derive(Debug)]
 pub enum BuilderError {
     InvalidBase64 { source: String, error: base64::DecodeError, },
     CaptchaImageIsNotProvided,
+    MinLenTooBig { min_len: u8, },
+    MaxLenTooBig { max_len: u8, },
+    MaxLenLessThanMinLen { min_len: u8, max_len: u8, },
+    InvalidLang { lang: String, },
+}
+
+impl Default for CaptchaBuilder {
+    fn default() -> CaptchaBuilder {
+        CaptchaBuilder::new()
+    }
 }
 
 impl CaptchaBuilder {
     pub fn new() -> CaptchaBuilder {
         CaptchaBuilder {
-            maybe_captcha_data: None,
+            image: ImageSource::default(),
             is_case_sensitive: false,
+            options: Options::default(),
         }
     }
 
-    pub fn set_upload_file<P>(mut self, path: P) -> Self where P: AsRef<Path> {
-        self.maybe_captcha_data = Some(CaptchaData::UploadFile(path.as_ref().to_owned()));
+    pub fn set_case_sensitive(mut self, is_case_sensitive: bool) -> Self {
+        self.is_case_sensitive = is_case_sensitive;
         self
     }
 
-    pub fn set_image_data_base64<T>(mut self, base64_str: T) -> Result<Self, BuilderError> where T: AsRef<str> {
-        base64::decode(base64_str.as_ref())
-            .map_err(|error| {
-                BuilderError::InvalidBase64 {
-                    source: base64_str.as_ref().to_string(),
-                    error,
-                }
-            })?;
-        self.maybe_captcha_data = Some(CaptchaData::Base64(base64_str.as_ref().to_string()));
-        Ok(self)
+    pub fn set_phrase(mut self, is_phrase: bool) -> Self {
+        self.options.is_phrase = is_phrase;
+        self
     }
 
-    pub fn set_image_data_encode_as_base64<T>(mut self, image_data: T) -> Self where T: AsRef<[u8]> {
-        let base64_string = base64::encode(image_data.as_ref());
-        self.maybe_captcha_data = Some(CaptchaData::Base64(base64_string));
+    pub fn set_numeric(mut self, numeric: Numeric) -> Self {
+        self.options.maybe_numeric = Some(numeric);
         self
     }
 
-    pub fn set_case_sensitive(mut self, is_case_sensitive: bool) -> Self {
-        self.is_case_sensitive = is_case_sensitive;
+    pub fn set_calc(mut self, is_calc: bool) -> Self {
+        self.options.is_calc = is_calc;
+        self
+    }
+
+    pub fn set_min_len(mut self, min_len: u8) -> Self {
+        self.options.maybe_min_len = Some(min_len);
         self
     }
 
-    pub fn finish(self) -> Result<Captcha, BuilderError> {
-        if let Some(captcha_data) = self.maybe_captcha_data {
-            Ok(Captcha {
-                captcha_data,
-                is_case_sensitive: self.is_case_sensitive,
-            })
-        } else {
-            Err(BuilderError::CaptchaImageIsNotProvided)
+    pub fn set_max_len(mut self, max_len: u8) -> Self {
+        self.options.maybe_max_len = Some(max_len);
+        self
+    }
+
+    pub fn set_language(mut self, language: Language) -> Self {
+        self.options.maybe_language = Some(language);
+        self
+    }
+
+    pub fn set_lang<T>(mut self, lang: T) -> Self where T: AsRef<str> {
+        self.options.maybe_lang = Some(lang.as_ref().to_string());
+        self
+    }
+
+    pub fn finish(mut self) -> Result<Captcha, BuilderError> {
+        let captcha_data = self.image.maybe_captcha_data
+            .ok_or(BuilderError::CaptchaImageIsNotProvided)?;
+        self.options.maybe_text_instructions = self.image.maybe_text_instructions;
+        self.options.maybe_image_instructions = self.image.maybe_image_instructions;
+        if let Some(min_len) = self.options.maybe_min_len {
+            if min_len > MAX_LEN_LIMIT {
+                return Err(BuilderError::MinLenTooBig { min_len, });
+            }
+        }
+        if let Some(max_len) = self.options.maybe_max_len {
+            if max_len > MAX_LEN_LIMIT {
+                return Err(BuilderError::MaxLenTooBig { max_len, });
+            }
+        }
+        if let (Some(min_len), Some(max_len)) = (self.options.maybe_min_len, self.options.maybe_max_len) {
+            if max_len != 0 && max_len < min_len {
+                return Err(BuilderError::MaxLenLessThanMinLen { min_len, max_len, });
+            }
+        }
+        if let Some(lang) = &self.options.maybe_lang {
+            if lang.len() != 2 || !lang.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(BuilderError::InvalidLang { lang: lang.clone(), });
+            }
+        }
+        Ok(Captcha {
+            captcha_data,
+            is_case_sensitive: self.is_case_sensitive,
+            options: self.options,
+        })
+    }
+}
+
+impl_image_setters!(CaptchaBuilder);
+
+impl Numeric {
+    fn code(&self) -> u8 {
+        match self {
+            Numeric::NotSpecified => 0,
+            Numeric::NumbersOnly => 1,
+            Numeric::LettersOnly => 2,
+            Numeric::NumbersOrLetters => 3,
+            Numeric::NumbersAndLetters => 4,
+        }
+    }
+}
+
+impl Language {
+    fn code(&self) -> u8 {
+        match self {
+            Language::NotSpecified => 0,
+            Language::Cyrillic => 1,
+            Language::Latin => 2,
         }
     }
 }
@@ -103,52 +269,227 @@ impl CaptchaBuilder {
 #[derive(Debug)]
 pub enum PrepareRequestError {
     CaptchaImageFileOpen { filename: PathBuf, error: std::io::Error, },
+    CaptchaImageFileRead { filename: PathBuf, error: std::io::Error, },
+    UnsupportedLanguagePool { lang: String, },
 }
 
-#[async_trait]
-impl CaptchaRequest for Captcha {
-    type PrepareRequestError = PrepareRequestError;
+impl CaptchaData {
+    pub(crate) fn upload_file<P>(path: P) -> CaptchaData where P: AsRef<Path> {
+        CaptchaData::UploadFile(path.as_ref().to_owned())
+    }
 
-    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
-        match &self.captcha_data {
+    pub(crate) fn base64(base64_str: &str) -> Result<CaptchaData, base64::DecodeError> {
+        base64::decode(base64_str)?;
+        Ok(CaptchaData::Base64(base64_str.to_string()))
+    }
+
+    pub(crate) fn encode_as_base64<T>(data: T) -> CaptchaData where T: AsRef<[u8]> {
+        CaptchaData::Base64(base64::encode(data.as_ref()))
+    }
+
+    async fn file_part(path_buf: &Path) -> Result<multipart::Part, PrepareRequestError> {
+        let file = File::open(path_buf).await
+            .map_err(|error| {
+                PrepareRequestError::CaptchaImageFileOpen {
+                    filename: path_buf.to_owned(),
+                    error,
+                }
+            })?;
+        let stream = FramedRead::new(file, BytesCodec::new());
+
+        let part = multipart::Part::stream(Body::wrap_stream(stream))
+            .file_name(path_buf.to_string_lossy().to_string());
+        Ok(part)
+    }
+
+    pub(crate) async fn to_base64(&self) -> Result<String, PrepareRequestError> {
+        match self {
             CaptchaData::UploadFile(path_buf) => {
-                let file = File::open(path_buf).await
+                let data = tokio::fs::read(path_buf).await
                     .map_err(|error| {
-                        PrepareRequestError::CaptchaImageFileOpen {
+                        PrepareRequestError::CaptchaImageFileRead {
                             filename: path_buf.clone(),
                             error,
                         }
                     })?;
-                let stream = FramedRead::new(file, BytesCodec::new());
+                Ok(base64::encode(data))
+            },
+            CaptchaData::Base64(base64_string) =>
+                Ok(base64_string.clone()),
+        }
+    }
+
+    pub(crate) async fn maybe_to_base64(maybe_captcha_data: Option<&CaptchaData>) -> Result<Option<String>, PrepareRequestError> {
+        match maybe_captcha_data {
+            Some(captcha_data) =>
+                Ok(Some(captcha_data.to_base64().await?)),
+            None =>
+                Ok(None),
+        }
+    }
 
-                let image_file_part = multipart::Part::stream(Body::wrap_stream(stream))
-                    .file_name(path_buf.to_string_lossy().to_string());
+    pub(crate) async fn prepare_request(
+        &self,
+        maybe_method: Option<&'static str>,
+        params: Vec<(&'static str, String)>,
+        attachments: Vec<(&'static str, &CaptchaData)>,
+        api_token: &ApiToken,
+        request_builder: RequestBuilder,
+    )
+        -> Result<RequestBuilder, PrepareRequestError>
+    {
+        let is_multipart = attachments.iter()
+            .all(|(_, attachment)| matches!(attachment, CaptchaData::UploadFile(..)));
+        match self {
+            CaptchaData::UploadFile(path_buf) if is_multipart => {
+                let image_file_part = CaptchaData::file_part(path_buf).await?;
 
                 log::debug!("building UploadFile request with {:?}", path_buf);
 
-                let form = multipart::Form::new()
-                    .text("method", "post")
+                let mut form = multipart::Form::new()
+                    .text("method", maybe_method.unwrap_or("post"))
                     .text("key", api_token.key.clone())
-                    .text("json", "1")
-                    .text("regsense", if self.is_case_sensitive { "1" } else { "0" })
-                    .part("file", image_file_part);
+                    .text("json", "1");
+                for (name, value) in params {
+                    form = form.text(name, value);
+                }
+                form = form.part("file", image_file_part);
+                for (name, attachment) in attachments {
+                    if let CaptchaData::UploadFile(attachment_path_buf) = attachment {
+                        form = form.part(name, CaptchaData::file_part(attachment_path_buf).await?);
+                    }
+                }
 
                 Ok(request_builder.multipart(form))
             },
-            CaptchaData::Base64(base64_string) => {
+            captcha_data => {
+                let base64_string = captcha_data.to_base64().await?;
 
                 log::debug!("building Base64 request with captcha base64.len = {}", base64_string.len());
 
-                let request_builder = request_builder
-                    .form(&[
-                        ("method", "base64"),
-                        ("key", &*api_token.key),
-                        ("json", "1"),
-                        ("regsense", if self.is_case_sensitive { "1" } else { "0" }),
-                        ("body", &*base64_string),
-                    ]);
-                Ok(request_builder)
+                let mut form = vec![
+                    ("method", maybe_method.unwrap_or("base64").to_string()),
+                    ("key", api_token.key.clone()),
+                    ("json", "1".to_string()),
+                ];
+                form.extend(params);
+                form.push(("body", base64_string));
+                for (name, attachment) in attachments {
+                    form.push((name, attachment.to_base64().await?));
+                }
+
+                Ok(request_builder.form(&form))
             },
         }
     }
 }
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    body: String,
+    phrase: bool,
+    case: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    numeric: Option<u8>,
+    math: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_length: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_length: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    img_instructions: Option<String>,
+}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "normal";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        let mut params = vec![
+            ("regsense", if self.is_case_sensitive { "1" } else { "0" }.to_string()),
+        ];
+        if self.options.is_phrase {
+            params.push(("phrase", "1".to_string()));
+        }
+        if let Some(numeric) = self.options.maybe_numeric {
+            params.push(("numeric", numeric.code().to_string()));
+        }
+        if self.options.is_calc {
+            params.push(("calc", "1".to_string()));
+        }
+        if let Some(min_len) = self.options.maybe_min_len {
+            params.push(("min_len", min_len.to_string()));
+        }
+        if let Some(max_len) = self.options.maybe_max_len {
+            params.push(("max_len", max_len.to_string()));
+        }
+        if let Some(language) = self.options.maybe_language {
+            params.push(("language", language.code().to_string()));
+        }
+        if let Some(lang) = &self.options.maybe_lang {
+            params.push(("lang", lang.clone()));
+        }
+        if let Some(text_instructions) = &self.options.maybe_text_instructions {
+            params.push(("textinstructions", text_instructions.clone()));
+        }
+        let mut attachments = vec![];
+        if let Some(image_instructions) = &self.options.maybe_image_instructions {
+            attachments.push(("imginstructions", image_instructions));
+        }
+        self.captcha_data.prepare_request(None, params, attachments, api_token, request_builder).await
+    }
+
+    fn language_pool(&self) -> Result<Option<&'static str>, Self::PrepareRequestError> {
+        match (&self.options.maybe_lang, self.options.maybe_language) {
+            (Some(lang), _) =>
+                task::language_pool(lang)
+                    .map(Some)
+                    .ok_or_else(|| PrepareRequestError::UnsupportedLanguagePool { lang: lang.clone(), }),
+            (None, Some(Language::Cyrillic)) =>
+                Ok(Some("rn")),
+            (None, Some(Language::Latin)) =>
+                Ok(Some("en")),
+            (None, Some(Language::NotSpecified)) | (None, None) =>
+                Ok(None),
+        }
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: "ImageToTextTask",
+            body: self.captcha_data.to_base64().await?,
+            phrase: self.options.is_phrase,
+            case: self.is_case_sensitive,
+            numeric: self.options.maybe_numeric.map(|numeric| numeric.code()),
+            math: self.options.is_calc,
+            min_length: self.options.maybe_min_len,
+            max_length: self.options.maybe_max_len,
+            comment: self.options.maybe_text_instructions.clone(),
+            img_instructions: CaptchaData::maybe_to_base64(self.options.maybe_image_instructions.as_ref()).await?,
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "text")
+    }
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(IMAGE_INITIAL_DELAY_MS)
+    }
+
+    fn recommended_poll_interval() -> Duration {
+        Duration::from_millis(IMAGE_POLL_INTERVAL_MS)
+    }
+}
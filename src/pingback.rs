@@ -0,0 +1,392 @@
+use std::{
+    io,
+    net::SocketAddr,
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use tokio::{
+    io::{
+        AsyncRead,
+        AsyncReadExt,
+        AsyncWriteExt,
+    },
+    net::{
+        TcpStream,
+        TcpListener,
+        ToSocketAddrs,
+    },
+    sync::{
+        oneshot,
+    },
+    task::{
+        JoinHandle,
+    },
+    time::{
+        timeout,
+    },
+};
+
+use crate::{
+    CaptchaId,
+};
+
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+const MAX_BODY_SIZE: usize = 64 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const ARRIVED_TTL: Duration = Duration::from_secs(600);
+const MAX_ARRIVED: usize = 1024;
+
+pub struct PingbackListener {
+    local_addr: SocketAddr,
+    shared: Arc<Shared>,
+    accept_task: JoinHandle<()>,
+}
+
+struct Shared {
+    pending: Mutex<HashMap<CaptchaId, Pending>>,
+    maybe_secret: Option<String>,
+}
+
+enum Pending {
+    Waiting(oneshot::Sender<String>),
+    Arrived { code: String, arrived_at: Instant, },
+}
+
+#[derive(Debug)]
+struct Pingback {
+    captcha_id: CaptchaId,
+    code: String,
+    maybe_secret: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ReadPingbackError {
+    Io(io::Error),
+    Timeout,
+    HeadTooLarge,
+    BodyTooLarge,
+    InvalidRequest,
+    InvalidContentLength,
+    MissingParameters,
+}
+
+impl From<io::Error> for ReadPingbackError {
+    fn from(error: io::Error) -> ReadPingbackError {
+        ReadPingbackError::Io(error)
+    }
+}
+
+impl PingbackListener {
+    pub async fn bind<A>(addr: A) -> Result<PingbackListener, io::Error> where A: ToSocketAddrs {
+        PingbackListener::start(addr, None).await
+    }
+
+    /// Like `bind`, but only accepts pingbacks carrying a matching `secret` parameter,
+    /// so the pingback url should look like `http://host:port/?secret=...`.
+    pub async fn bind_with_secret<A, S>(addr: A, secret: S) -> Result<PingbackListener, io::Error> where A: ToSocketAddrs, S: Into<String> {
+        PingbackListener::start(addr, Some(secret.into())).await
+    }
+
+    async fn start<A>(addr: A, maybe_secret: Option<String>) -> Result<PingbackListener, io::Error> where A: ToSocketAddrs {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            pending: Mutex::new(HashMap::new()),
+            maybe_secret,
+        });
+
+        log::debug!("pingback listener bound on {}", local_addr);
+
+        let accept_task = tokio::spawn(accept_loop(listener, shared.clone()));
+        Ok(PingbackListener { local_addr, shared, accept_task, })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub(crate) fn register(&self, captcha_id: &CaptchaId) -> oneshot::Receiver<String> {
+        let (sender, receiver) = oneshot::channel();
+        let mut pending = self.shared.pending.lock().unwrap();
+        match pending.remove(captcha_id) {
+            Some(Pending::Arrived { code, arrived_at, }) if arrived_at.elapsed() < ARRIVED_TTL => {
+                let _ = sender.send(code);
+            },
+            Some(..) | None => {
+                pending.insert(captcha_id.clone(), Pending::Waiting(sender));
+            },
+        }
+        receiver
+    }
+
+    pub(crate) fn unregister(&self, captcha_id: &CaptchaId) {
+        self.shared.pending.lock().unwrap().remove(captcha_id);
+    }
+}
+
+impl Drop for PingbackListener {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+impl Shared {
+    fn deliver(&self, captcha_id: CaptchaId, code: String) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.remove(&captcha_id) {
+            Some(Pending::Waiting(sender)) => {
+                if let Err(code) = sender.send(code) {
+                    // the waiter went away without unregistering: keep the answer for a while
+                    // in case the same captcha is waited on again
+                    pending.retain(|_, entry| match entry {
+                        Pending::Waiting(..) => true,
+                        Pending::Arrived { arrived_at, .. } => arrived_at.elapsed() < ARRIVED_TTL,
+                    });
+                    let arrived_count = pending.values()
+                        .filter(|entry| matches!(entry, Pending::Arrived { .. }))
+                        .count();
+                    if arrived_count < MAX_ARRIVED {
+                        pending.insert(captcha_id, Pending::Arrived { code, arrived_at: Instant::now(), });
+                    }
+                }
+                true
+            },
+            Some(arrived @ Pending::Arrived { .. }) => {
+                pending.insert(captcha_id, arrived);
+                false
+            },
+            None =>
+                false,
+        }
+    }
+}
+
+async fn accept_loop(listener: TcpListener, shared: Arc<Shared>) {
+    loop {
+        let (stream, peer_addr) = match listener.accept().await {
+            Ok(accepted) =>
+                accepted,
+            Err(error) => {
+                log::warn!("pingback listener accept failed: {:?}", error);
+                continue;
+            },
+        };
+        let shared = shared.clone();
+        tokio::spawn(async move {
+            if let Err(error) = handle_connection(stream, &shared).await {
+                log::warn!("failed to process pingback from {}: {:?}", peer_addr, error);
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, shared: &Shared) -> Result<(), ReadPingbackError> {
+    let maybe_pingback = match timeout(READ_TIMEOUT, read_pingback(&mut stream)).await {
+        Ok(result) =>
+            result,
+        Err(..) =>
+            Err(ReadPingbackError::Timeout),
+    };
+    let response: &[u8] = match &maybe_pingback {
+        Ok(pingback) if shared.maybe_secret.is_some() && pingback.maybe_secret != shared.maybe_secret =>
+            b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        Ok(pingback) => {
+            log::debug!("pingback received for captcha id = {}", pingback.captcha_id);
+            if shared.deliver(pingback.captcha_id.clone(), pingback.code.clone()) {
+                b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nOK"
+            } else {
+                log::debug!("ignoring pingback for unknown captcha id = {}", pingback.captcha_id);
+                b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            }
+        },
+        Err(..) =>
+            b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+    };
+    timeout(WRITE_TIMEOUT, async {
+        stream.write_all(response).await?;
+        stream.shutdown().await
+    }).await.map_err(|_| ReadPingbackError::Timeout)??;
+
+    maybe_pingback.map(|_| ())
+}
+
+async fn read_pingback<S>(stream: &mut S) -> Result<Pingback, ReadPingbackError> where S: AsyncRead + Unpin {
+    let mut buffer = Vec::new();
+    let head_end = loop {
+        if let Some(offset) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break offset + 4;
+        }
+        if buffer.len() > MAX_HEAD_SIZE {
+            return Err(ReadPingbackError::HeadTooLarge);
+        }
+        let mut chunk = [0; 4096];
+        let bytes_read = stream.read(&mut chunk).await?;
+        if bytes_read == 0 {
+            return Err(ReadPingbackError::InvalidRequest);
+        }
+        buffer.extend_from_slice(&chunk[.. bytes_read]);
+    };
+
+    let head = std::str::from_utf8(&buffer[.. head_end])
+        .map_err(|_| ReadPingbackError::InvalidRequest)?;
+    let mut lines = head.split("\r\n");
+    let target = lines.next()
+        .and_then(|request_line| request_line.split(' ').nth(1))
+        .ok_or(ReadPingbackError::InvalidRequest)?
+        .to_string();
+    let mut content_length = 0;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()
+                    .map_err(|_| ReadPingbackError::InvalidContentLength)?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return Err(ReadPingbackError::BodyTooLarge);
+    }
+
+    let mut body = buffer.split_off(head_end);
+    if body.len() < content_length {
+        let mut rest = vec![0; content_length - body.len()];
+        stream.read_exact(&mut rest).await?;
+        body.extend_from_slice(&rest);
+    }
+    body.truncate(content_length);
+
+    let mut params: Vec<(String, String)> = match target.split_once('?') {
+        Some((_, query)) =>
+            serde_urlencoded::from_str(query).unwrap_or_default(),
+        None =>
+            Vec::new(),
+    };
+    if body.first() == Some(&b'{') {
+        if let Ok(serde_json::Value::Object(object)) = serde_json::from_slice(&body) {
+            for (key, value) in object {
+                let value = match value {
+                    serde_json::Value::String(value) =>
+                        value,
+                    other =>
+                        other.to_string(),
+                };
+                params.push((key, value));
+            }
+        }
+    } else {
+        params.extend(serde_urlencoded::from_bytes::<Vec<(String, String)>>(&body).unwrap_or_default());
+    }
+
+    let find_param = |name: &str| params.iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.clone());
+    match (find_param("id"), find_param("code")) {
+        (Some(captcha_id), Some(code)) =>
+            Ok(Pingback { captcha_id: CaptchaId::from(captcha_id), code, maybe_secret: find_param("secret"), }),
+        _ =>
+            Err(ReadPingbackError::MissingParameters),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read_request(request: &'static [u8]) -> Result<Pingback, ReadPingbackError> {
+        let (mut client, mut server) = tokio::io::duplex(MAX_HEAD_SIZE);
+        client.write_all(request).await.unwrap();
+        read_pingback(&mut server).await
+    }
+
+    #[tokio::test]
+    async fn read_pingback_form_body() {
+        let pingback = read_request(b"POST / HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: 22\r\n\r\nid=12345&code=a%2Bb+cd").await.unwrap();
+        assert_eq!(pingback.captcha_id, CaptchaId::from("12345".to_string()));
+        assert_eq!(pingback.code, "a+b cd");
+        assert_eq!(pingback.maybe_secret, None);
+    }
+
+    #[tokio::test]
+    async fn read_pingback_json_body() {
+        let pingback = read_request(b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 29\r\n\r\n{\"id\":12345,\"code\":\"03AGdBq\"}").await.unwrap();
+        assert_eq!(pingback.captcha_id, CaptchaId::from("12345".to_string()));
+        assert_eq!(pingback.code, "03AGdBq");
+    }
+
+    #[tokio::test]
+    async fn read_pingback_query_string() {
+        let pingback = read_request(b"GET /?secret=s3cr3t&id=777&code=abc HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        assert_eq!(pingback.captcha_id, CaptchaId::from("777".to_string()));
+        assert_eq!(pingback.code, "abc");
+        assert_eq!(pingback.maybe_secret.as_deref(), Some("s3cr3t"));
+    }
+
+    #[tokio::test]
+    async fn read_pingback_missing_parameters() {
+        assert!(matches!(
+            read_request(b"POST / HTTP/1.1\r\nContent-Length: 8\r\n\r\nid=12345").await,
+            Err(ReadPingbackError::MissingParameters)
+        ));
+    }
+
+    #[tokio::test]
+    async fn read_pingback_body_after_first_read() {
+        let (mut client, mut server) = tokio::io::duplex(MAX_HEAD_SIZE);
+        let writer = tokio::spawn(async move {
+            client.write_all(b"POST / HTTP/1.1\r\nContent-Length: 20\r\n\r\nid=1").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            client.write_all(b"2345&code=answer").await.unwrap();
+            client
+        });
+        let pingback = read_pingback(&mut server).await.unwrap();
+        writer.await.unwrap();
+        assert_eq!(pingback.captcha_id, CaptchaId::from("12345".to_string()));
+        assert_eq!(pingback.code, "answer");
+    }
+
+    async fn send_pingback(addr: SocketAddr, target: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!("POST {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", target, body.len(), body);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn listener_accepts_only_registered_ids_with_secret() {
+        let listener = PingbackListener::bind_with_secret("127.0.0.1:0", "s3cr3t").await.unwrap();
+        let addr = listener.local_addr();
+        let receiver = listener.register(&CaptchaId::from("1".to_string()));
+
+        let response = send_pingback(addr, "/?secret=wrong", "id=1&code=forged").await;
+        assert!(response.starts_with("HTTP/1.1 403"), "{}", response);
+        let response = send_pingback(addr, "/?secret=s3cr3t", "id=2&code=unknown").await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+        assert!(listener.shared.pending.lock().unwrap().get(&CaptchaId::from("2".to_string())).is_none());
+
+        let response = send_pingback(addr, "/?secret=s3cr3t", "id=1&code=answer").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert_eq!(receiver.await.unwrap(), "answer");
+    }
+
+    #[tokio::test]
+    async fn pingback_for_dropped_waiter_is_kept_for_next_register() {
+        let listener = PingbackListener::bind("127.0.0.1:0").await.unwrap();
+        let captcha_id = CaptchaId::from("1".to_string());
+        drop(listener.register(&captcha_id));
+
+        assert!(listener.shared.deliver(captcha_id.clone(), "answer".to_string()));
+        assert_eq!(listener.register(&captcha_id).await.unwrap(), "answer");
+        assert!(listener.shared.pending.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+use serde_derive::{
+    Serialize,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ProxyType {
+    Http,
+    Https,
+    Socks4,
+    Socks5,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Proxy {
+    proxy_type: ProxyType,
+    address: String,
+    port: u16,
+    maybe_auth: Option<ProxyAuth>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TaskProxy {
+    proxy_type: &'static str,
+    proxy_address: String,
+    proxy_port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy_login: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy_password: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct ProxyAuth {
+    login: String,
+    password: String,
+}
+
+impl ProxyType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProxyType::Http => "HTTP",
+            ProxyType::Https => "HTTPS",
+            ProxyType::Socks4 => "SOCKS4",
+            ProxyType::Socks5 => "SOCKS5",
+        }
+    }
+}
+
+impl Proxy {
+    pub fn new<T>(proxy_type: ProxyType, address: T, port: u16) -> Proxy where T: AsRef<str> {
+        Proxy {
+            proxy_type,
+            address: address.as_ref().to_string(),
+            port,
+            maybe_auth: None,
+        }
+    }
+
+    pub fn set_auth<L, P>(mut self, login: L, password: P) -> Self where L: AsRef<str>, P: AsRef<str> {
+        self.maybe_auth = Some(ProxyAuth {
+            login: login.as_ref().to_string(),
+            password: password.as_ref().to_string(),
+        });
+        self
+    }
+
+    pub fn proxy_type(&self) -> ProxyType {
+        self.proxy_type
+    }
+
+    pub(crate) fn proxy_param(&self) -> String {
+        match &self.maybe_auth {
+            Some(ProxyAuth { login, password, }) =>
+                format!("{}:{}@{}:{}", login, password, self.address, self.port),
+            None =>
+                format!("{}:{}", self.address, self.port),
+        }
+    }
+
+    pub(crate) fn task_proxy(&self) -> TaskProxy {
+        TaskProxy {
+            proxy_type: match self.proxy_type {
+                ProxyType::Http => "http",
+                ProxyType::Https => "https",
+                ProxyType::Socks4 => "socks4",
+                ProxyType::Socks5 => "socks5",
+            },
+            proxy_address: self.address.clone(),
+            proxy_port: self.port,
+            proxy_login: self.maybe_auth.as_ref().map(|auth| auth.login.clone()),
+            proxy_password: self.maybe_auth.as_ref().map(|auth| auth.password.clone()),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    time::{
+        Duration,
+    },
+};
+
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    RECAPTCHA_INITIAL_DELAY_MS,
+    proxy::{
+        Proxy,
+        TaskProxy,
+    },
+};
+
+pub struct Captcha {
+    googlekey: String,
+    pageurl: String,
+    is_invisible: bool,
+    is_enterprise: bool,
+    maybe_data_s: Option<String>,
+    cookies: Vec<(String, String)>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_googlekey: Option<String>,
+    maybe_pageurl: Option<String>,
+    is_invisible: bool,
+    is_enterprise: bool,
+    maybe_data_s: Option<String>,
+    cookies: Vec<(String, String)>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    GooglekeyIsNotProvided,
+    PageurlIsNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_googlekey<T>(mut self, googlekey: T) -> Self where T: AsRef<str> {
+        self.maybe_googlekey = Some(googlekey.as_ref().to_string());
+        self
+    }
+
+    pub fn set_pageurl<T>(mut self, pageurl: T) -> Self where T: AsRef<str> {
+        self.maybe_pageurl = Some(pageurl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_invisible(mut self, is_invisible: bool) -> Self {
+        self.is_invisible = is_invisible;
+        self
+    }
+
+    pub fn set_enterprise(mut self, is_enterprise: bool) -> Self {
+        self.is_enterprise = is_enterprise;
+        self
+    }
+
+    pub fn set_data_s<T>(mut self, data_s: T) -> Self where T: AsRef<str> {
+        self.maybe_data_s = Some(data_s.as_ref().to_string());
+        self
+    }
+
+    pub fn add_cookie<K, V>(mut self, name: K, value: V) -> Self where K: AsRef<str>, V: AsRef<str> {
+        self.cookies.push((name.as_ref().to_string(), value.as_ref().to_string()));
+        self
+    }
+
+    pub fn set_user_agent<T>(mut self, user_agent: T) -> Self where T: AsRef<str> {
+        self.maybe_user_agent = Some(user_agent.as_ref().to_string());
+        self
+    }
+
+    pub fn set_proxy(mut self, proxy: Proxy) -> Self {
+        self.maybe_proxy = Some(proxy);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let googlekey = self.maybe_googlekey
+            .filter(|googlekey| !googlekey.is_empty())
+            .ok_or(BuilderError::GooglekeyIsNotProvided)?;
+        let pageurl = self.maybe_pageurl
+            .filter(|pageurl| !pageurl.is_empty())
+            .ok_or(BuilderError::PageurlIsNotProvided)?;
+        Ok(Captcha {
+            googlekey,
+            pageurl,
+            is_invisible: self.is_invisible,
+            is_enterprise: self.is_enterprise,
+            maybe_data_s: self.maybe_data_s,
+            cookies: self.cookies,
+            maybe_user_agent: self.maybe_user_agent,
+            maybe_proxy: self.maybe_proxy,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    #[serde(rename = "websiteURL")]
+    website_url: String,
+    website_key: String,
+    is_invisible: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recaptcha_data_s_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cookies: Option<String>,
+    #[serde(flatten)]
+    maybe_proxy: Option<TaskProxy>,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "recaptcha_v2";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!("building userrecaptcha request for googlekey = {} on {}", self.googlekey, self.pageurl);
+
+        let mut form = vec![
+            ("method", "userrecaptcha".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("googlekey", self.googlekey.clone()),
+            ("pageurl", self.pageurl.clone()),
+        ];
+        if self.is_invisible {
+            form.push(("invisible", "1".to_string()));
+        }
+        if self.is_enterprise {
+            form.push(("enterprise", "1".to_string()));
+        }
+        if let Some(data_s) = &self.maybe_data_s {
+            form.push(("data-s", data_s.clone()));
+        }
+        if !self.cookies.is_empty() {
+            let cookies: Vec<_> = self.cookies.iter()
+                .map(|(name, value)| format!("{}:{}", name, value))
+                .collect();
+            form.push(("cookies", cookies.join(";")));
+        }
+        if let Some(user_agent) = &self.maybe_user_agent {
+            form.push(("userAgent", user_agent.clone()));
+        }
+        if let Some(proxy) = &self.maybe_proxy {
+            form.push(("proxy", proxy.proxy_param()));
+            form.push(("proxytype", proxy.proxy_type().as_str().to_string()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        let task_type = match (self.is_enterprise, self.maybe_proxy.is_some()) {
+            (false, false) => "RecaptchaV2TaskProxyless",
+            (false, true) => "RecaptchaV2Task",
+            (true, false) => "RecaptchaV2EnterpriseTaskProxyless",
+            (true, true) => "RecaptchaV2EnterpriseTask",
+        };
+        let maybe_cookies = if self.cookies.is_empty() {
+            None
+        } else {
+            let cookies: Vec<_> = self.cookies.iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            Some(cookies.join("; "))
+        };
+        Ok(Task {
+            task_type,
+            website_url: self.pageurl.clone(),
+            website_key: self.googlekey.clone(),
+            is_invisible: self.is_invisible,
+            recaptcha_data_s_value: self.maybe_data_s.clone(),
+            user_agent: self.maybe_user_agent.clone(),
+            cookies: maybe_cookies,
+            maybe_proxy: self.maybe_proxy.as_ref().map(Proxy::task_proxy),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "gRecaptchaResponse")
+    }
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(RECAPTCHA_INITIAL_DELAY_MS)
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    time::{
+        Duration,
+    },
+};
+
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    RECAPTCHA_INITIAL_DELAY_MS,
+};
+
+pub const MIN_SCORE_LOWEST: f32 = 0.1;
+pub const MIN_SCORE_HIGHEST: f32 = 0.9;
+
+pub struct Captcha {
+    googlekey: String,
+    pageurl: String,
+    maybe_action: Option<String>,
+    maybe_min_score: Option<f32>,
+    is_enterprise: bool,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_googlekey: Option<String>,
+    maybe_pageurl: Option<String>,
+    maybe_action: Option<String>,
+    maybe_min_score: Option<f32>,
+    is_enterprise: bool,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    GooglekeyIsNotProvided,
+    PageurlIsNotProvided,
+    InvalidAction { action: String, },
+    MinScoreOutOfRange { min_score: f32, },
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_googlekey<T>(mut self, googlekey: T) -> Self where T: AsRef<str> {
+        self.maybe_googlekey = Some(googlekey.as_ref().to_string());
+        self
+    }
+
+    pub fn set_pageurl<T>(mut self, pageurl: T) -> Self where T: AsRef<str> {
+        self.maybe_pageurl = Some(pageurl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_action<T>(mut self, action: T) -> Self where T: AsRef<str> {
+        self.maybe_action = Some(action.as_ref().to_string());
+        self
+    }
+
+    pub fn set_min_score(mut self, min_score: f32) -> Self {
+        self.maybe_min_score = Some(min_score);
+        self
+    }
+
+    pub fn set_enterprise(mut self, is_enterprise: bool) -> Self {
+        self.is_enterprise = is_enterprise;
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let googlekey = self.maybe_googlekey
+            .filter(|googlekey| !googlekey.is_empty())
+            .ok_or(BuilderError::GooglekeyIsNotProvided)?;
+        let pageurl = self.maybe_pageurl
+            .filter(|pageurl| !pageurl.is_empty())
+            .ok_or(BuilderError::PageurlIsNotProvided)?;
+        if let Some(action) = &self.maybe_action {
+            let is_valid = !action.is_empty() && action.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '/' || c == '_');
+            if !is_valid {
+                return Err(BuilderError::InvalidAction { action: action.clone(), });
+            }
+        }
+        if let Some(min_score) = self.maybe_min_score {
+            if !(MIN_SCORE_LOWEST ..= MIN_SCORE_HIGHEST).contains(&min_score) {
+                return Err(BuilderError::MinScoreOutOfRange { min_score, });
+            }
+        }
+        Ok(Captcha {
+            googlekey,
+            pageurl,
+            maybe_action: self.maybe_action,
+            maybe_min_score: self.maybe_min_score,
+            is_enterprise: self.is_enterprise,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    #[serde(rename = "websiteURL")]
+    website_url: String,
+    website_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page_action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_score: Option<f32>,
+    is_enterprise: bool,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "recaptcha_v3";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!("building userrecaptcha v3 request for googlekey = {} on {}", self.googlekey, self.pageurl);
+
+        let mut form = vec![
+            ("method", "userrecaptcha".to_string()),
+            ("version", "v3".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("googlekey", self.googlekey.clone()),
+            ("pageurl", self.pageurl.clone()),
+        ];
+        if let Some(action) = &self.maybe_action {
+            form.push(("action", action.clone()));
+        }
+        if let Some(min_score) = self.maybe_min_score {
+            form.push(("min_score", min_score.to_string()));
+        }
+        if self.is_enterprise {
+            form.push(("enterprise", "1".to_string()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: "RecaptchaV3TaskProxyless",
+            website_url: self.pageurl.clone(),
+            website_key: self.googlekey.clone(),
+            page_action: self.maybe_action.clone(),
+            min_score: self.maybe_min_score,
+            is_enterprise: self.is_enterprise,
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "gRecaptchaResponse")
+    }
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(RECAPTCHA_INITIAL_DELAY_MS)
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    future::{
+        Future,
+        pending,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    time::{
+        sleep,
+    },
+};
+
+use crate::{
+    ApiError,
+    BalanceError,
+    BalanceResponseError,
+    CaptchaResponseError,
+    PollResponseError,
+    task::TaskResponseError,
+};
+
+pub const DEFAULT_RETRY_MAX_ATTEMPTS: u32 = 5;
+pub const DEFAULT_RETRY_MAX_ATTEMPTS_STR: &str = "5";
+pub const DEFAULT_RETRY_INITIAL_DELAY_MS: u64 = 1000;
+pub const DEFAULT_RETRY_INITIAL_DELAY_MS_STR: &str = "1000";
+pub const DEFAULT_RETRY_MAX_DELAY_MS: u64 = 30000;
+pub const DEFAULT_RETRY_MAX_DELAY_MS_STR: &str = "30000";
+pub const DEFAULT_RETRY_MULTIPLIER: u32 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ErrorClass {
+    Network,
+    ServerError,
+    NoSlotAvailable,
+    MaxUserTurn,
+    RequestLimitExceeded,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub multiplier: u32,
+    pub is_jitter: bool,
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: DEFAULT_RETRY_MAX_ATTEMPTS,
+            initial_delay_ms: DEFAULT_RETRY_INITIAL_DELAY_MS,
+            max_delay_ms: DEFAULT_RETRY_MAX_DELAY_MS,
+            multiplier: DEFAULT_RETRY_MULTIPLIER,
+            is_jitter: true,
+            retry_on: vec![
+                ErrorClass::Network,
+                ErrorClass::ServerError,
+                ErrorClass::NoSlotAvailable,
+                ErrorClass::MaxUserTurn,
+                ErrorClass::RequestLimitExceeded,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn no_retry() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay_ms = (self.multiplier.max(1) as u64)
+            .checked_pow(attempt.saturating_sub(1))
+            .and_then(|factor| self.initial_delay_ms.checked_mul(factor))
+            .unwrap_or(self.max_delay_ms)
+            .min(self.max_delay_ms);
+        let delay_ms = if self.is_jitter {
+            delay_ms / 2 + fastrand::u64(0 ..= delay_ms - delay_ms / 2)
+        } else {
+            delay_ms
+        };
+        Duration::from_millis(delay_ms)
+    }
+
+    pub(crate) async fn run<T, E, F, R>(&self, attempt_fn: F) -> Result<T, ApiError<E>>
+    where F: FnMut() -> R,
+          R: Future<Output = Result<T, ApiError<E>>>,
+    {
+        self.run_until(attempt_fn, pending()).await
+    }
+
+    /// Like `run`, but gives up with the error produced by `interrupted` if it resolves during a backoff sleep.
+    /// Attempts themselves are never interrupted.
+    pub(crate) async fn run_until<T, E, F, R, I>(&self, mut attempt_fn: F, interrupted: I) -> Result<T, ApiError<E>>
+    where F: FnMut() -> R,
+          R: Future<Output = Result<T, ApiError<E>>>,
+          I: Future<Output = ApiError<E>>,
+    {
+        tokio::pin!(interrupted);
+        let mut attempt = 1;
+        loop {
+            match attempt_fn().await {
+                Err(error) if attempt < self.max_attempts => {
+                    match error.error_class() {
+                        Some(error_class) if self.retry_on.contains(&error_class) => {
+                            let delay = self.delay(attempt);
+                            log::warn!(
+                                "attempt {}/{} failed with {:?} error, retrying in {:?}",
+                                attempt,
+                                self.max_attempts,
+                                error_class,
+                                delay,
+                            );
+                            tokio::select! {
+                                biased;
+                                error = &mut interrupted =>
+                                    return Err(error),
+                                () = sleep(delay) =>
+                                    attempt += 1,
+                            }
+                        },
+                        _ =>
+                            return Err(error),
+                    }
+                },
+                result =>
+                    return result,
+            }
+        }
+    }
+}
+
+impl<E> ApiError<E> {
+    pub fn error_class(&self) -> Option<ErrorClass> {
+        match self {
+            ApiError::SendCaptchaRequest(error) if error.is_connect() =>
+                Some(ErrorClass::Network),
+            ApiError::CaptchaResponse(CaptchaResponseError::NoSlotAvailable) |
+            ApiError::TaskResponse(TaskResponseError::NoSlotAvailable) =>
+                Some(ErrorClass::NoSlotAvailable),
+            ApiError::CaptchaResponse(CaptchaResponseError::MaxUserTurn) =>
+                Some(ErrorClass::MaxUserTurn),
+            ApiError::SendPollRequest(..) | ApiError::ReadPollResponse(..) =>
+                Some(ErrorClass::Network),
+            ApiError::SendPollRequestBadStatusCode { status_code, } if status_code.is_server_error() =>
+                Some(ErrorClass::ServerError),
+            ApiError::PollResponse(PollResponseError::RequestLimitExceeded { .. }) =>
+                Some(ErrorClass::RequestLimitExceeded),
+            ApiError::Balance(BalanceError::SendBalanceRequest(..)) |
+            ApiError::Balance(BalanceError::ReadBalanceResponse(..)) =>
+                Some(ErrorClass::Network),
+            ApiError::Balance(BalanceError::SendBalanceRequestBadStatusCode { status_code, }) if status_code.is_server_error() =>
+                Some(ErrorClass::ServerError),
+            ApiError::Balance(BalanceError::BalanceResponse(BalanceResponseError::PollResponse(PollResponseError::RequestLimitExceeded { .. }))) =>
+                Some(ErrorClass::RequestLimitExceeded),
+            _ =>
+                None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn policy(is_jitter: bool) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_delay_ms: 1,
+            max_delay_ms: 2,
+            is_jitter,
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn request_limit_exceeded() -> ApiError<()> {
+        ApiError::PollResponse(PollResponseError::RequestLimitExceeded { code: "1001".to_string(), })
+    }
+
+    #[test]
+    fn delay_grows_and_is_capped() {
+        let policy = RetryPolicy { is_jitter: false, ..RetryPolicy::default() };
+        assert_eq!(policy.delay(1), Duration::from_millis(1000));
+        assert_eq!(policy.delay(2), Duration::from_millis(2000));
+        assert_eq!(policy.delay(3), Duration::from_millis(4000));
+        assert_eq!(policy.delay(6), Duration::from_millis(30000));
+        assert_eq!(policy.delay(100), Duration::from_millis(30000));
+    }
+
+    #[test]
+    fn delay_jitter_stays_within_bounds() {
+        let policy = RetryPolicy::default();
+        for attempt in 1 ..= 8 {
+            let full_delay = RetryPolicy { is_jitter: false, ..RetryPolicy::default() }.delay(attempt);
+            for _ in 0 .. 100 {
+                let delay = policy.delay(attempt);
+                assert!(delay >= full_delay / 2 && delay <= full_delay, "{:?} not within half of {:?}", delay, full_delay);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn run_respects_max_attempts() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = policy(true)
+            .run(|| async {
+                attempts.set(attempts.get() + 1);
+                Err(request_limit_exceeded())
+            })
+            .await;
+        assert!(matches!(result, Err(ApiError::PollResponse(PollResponseError::RequestLimitExceeded { .. }))));
+        assert_eq!(attempts.get(), 4);
+    }
+
+    #[tokio::test]
+    async fn run_stops_on_success_and_non_retryable_errors() {
+        let attempts = Cell::new(0);
+        let result = policy(false)
+            .run(|| async {
+                attempts.set(attempts.get() + 1);
+                if attempts.get() < 3 { Err(request_limit_exceeded()) } else { Ok(attempts.get()) }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+
+        let attempts = Cell::new(0);
+        let result: Result<(), ApiError<()>> = policy(false)
+            .run(|| async {
+                attempts.set(attempts.get() + 1);
+                Err(ApiError::PollResponse(PollResponseError::ErrorWrongUserKey))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 1);
+
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = RetryPolicy { retry_on: vec![ErrorClass::Network], ..policy(false) }
+            .run(|| async {
+                attempts.set(attempts.get() + 1);
+                Err(request_limit_exceeded())
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 1);
+    }
+}
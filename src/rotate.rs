@@ -0,0 +1,153 @@
+use std::{
+    time::{
+        Duration,
+    },
+};
+
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    IMAGE_INITIAL_DELAY_MS,
+    IMAGE_POLL_INTERVAL_MS,
+    normal::{
+        CaptchaData,
+        ImageSource,
+        impl_image_setters,
+    },
+};
+
+pub use crate::normal::PrepareRequestError;
+
+pub struct Captcha {
+    captcha_data: CaptchaData,
+    maybe_angle: Option<u16>,
+    maybe_text_instructions: Option<String>,
+    maybe_image_instructions: Option<CaptchaData>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    image: ImageSource,
+    maybe_angle: Option<u16>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    InvalidBase64 { source: String, error: base64::DecodeError, },
+    CaptchaImageIsNotProvided,
+    InvalidAngle { angle: u16, },
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_angle(mut self, angle: u16) -> Self {
+        self.maybe_angle = Some(angle);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let ImageSource { maybe_captcha_data, maybe_text_instructions, maybe_image_instructions, } = self.image;
+        let captcha_data = maybe_captcha_data
+            .ok_or(BuilderError::CaptchaImageIsNotProvided)?;
+        if let Some(angle) = self.maybe_angle {
+            if angle == 0 || angle > 360 {
+                return Err(BuilderError::InvalidAngle { angle, });
+            }
+        }
+        Ok(Captcha {
+            captcha_data,
+            maybe_angle: self.maybe_angle,
+            maybe_text_instructions,
+            maybe_image_instructions,
+        })
+    }
+}
+
+impl_image_setters!(CaptchaBuilder);
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    angle: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    img_instructions: Option<String>,
+}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = i32;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "rotate";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        let mut params = vec![];
+        if let Some(angle) = self.maybe_angle {
+            params.push(("angle", angle.to_string()));
+        }
+        if let Some(text_instructions) = &self.maybe_text_instructions {
+            params.push(("textinstructions", text_instructions.clone()));
+        }
+        let mut attachments = vec![];
+        if let Some(image_instructions) = &self.maybe_image_instructions {
+            attachments.push(("imginstructions", image_instructions));
+        }
+        self.captcha_data.prepare_request(Some("rotatecaptcha"), params, attachments, api_token, request_builder).await
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: "RotateTask",
+            body: self.captcha_data.to_base64().await?,
+            angle: self.maybe_angle,
+            comment: self.maybe_text_instructions.clone(),
+            img_instructions: CaptchaData::maybe_to_base64(self.maybe_image_instructions.as_ref()).await?,
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        answer.trim().parse()
+            .map_err(|_| DecodeAnswerError::InvalidFormat { source: answer, })
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        let maybe_angle = solution.get("rotate")
+            .and_then(serde_json::Value::as_i64)
+            .and_then(|angle| angle.try_into().ok());
+        match maybe_angle {
+            Some(angle) =>
+                Ok(angle),
+            None =>
+                Err(DecodeAnswerError::InvalidFormat { source: solution.to_string(), }),
+        }
+    }
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(IMAGE_INITIAL_DELAY_MS)
+    }
+
+    fn recommended_poll_interval() -> Duration {
+        Duration::from_millis(IMAGE_POLL_INTERVAL_MS)
+    }
+}
@@ -0,0 +1,191 @@
+use serde_derive::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::{
+    DecodeApiResponse,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateTaskRequest<'a, T> {
+    pub client_key: &'a str,
+    pub task: &'a T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_pool: Option<&'static str>,
+}
+
+pub(crate) fn language_pool(lang: &str) -> Option<&'static str> {
+    match lang.to_ascii_lowercase().as_str() {
+        "en" =>
+            Some("en"),
+        "ru" | "uk" | "be" | "kk" =>
+            Some("rn"),
+        _ =>
+            None,
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GetTaskResultRequest<'a> {
+    pub client_key: &'a str,
+    pub task_id: serde_json::Value,
+}
+
+impl<'a> GetTaskResultRequest<'a> {
+    pub(crate) fn new(client_key: &'a str, task_id: &str) -> GetTaskResultRequest<'a> {
+        let task_id = match task_id.parse::<u64>() {
+            Ok(numeric_id) =>
+                serde_json::Value::from(numeric_id),
+            Err(..) =>
+                serde_json::Value::from(task_id),
+        };
+        GetTaskResultRequest { client_key, task_id, }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GetBalanceRequest<'a> {
+    pub client_key: &'a str,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskApiResponse {
+    pub error_id: i32,
+    #[serde(default)]
+    pub error_code: Option<String>,
+    #[serde(default)]
+    pub error_description: Option<String>,
+    #[serde(default)]
+    pub task_id: Option<serde_json::Value>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub solution: Option<serde_json::Value>,
+    #[serde(default)]
+    pub balance: Option<serde_json::Value>,
+}
+
+#[derive(Debug)]
+pub enum TaskResponseError {
+    KeyDoesNotExist,
+    NoSlotAvailable,
+    ZeroCaptchaFilesize,
+    TooBigCaptchaFilesize,
+    ZeroBalance,
+    IpNotAllowed,
+    CaptchaUnsolvable,
+    BadDuplicates,
+    NoSuchMethod,
+    ImageTypeNotSupported,
+    NoSuchCaptchaId,
+    IpBlocked,
+    TaskAbsent,
+    TaskNotSupported,
+    RecaptchaInvalidSitekey,
+    AccountSuspended,
+    BadParameters,
+    BadImginstructions,
+    BadProxy,
+    ProxyConnectionFailed,
+    TokenExpired,
+    WrongCaptchaId,
+    UnexpectedApiResponse(Box<TaskApiResponse>),
+}
+
+pub(crate) enum TaskPollResult {
+    NotReady,
+    Ready { solution: serde_json::Value, },
+}
+
+impl TaskApiResponse {
+    pub(crate) fn parse(api_response_str: &str) -> Result<TaskApiResponse, DecodeApiResponse> {
+        serde_json::from_str(api_response_str)
+            .map_err(|error| DecodeApiResponse::UnexpectedResponse {
+                source: api_response_str.to_string(),
+                error,
+            })
+    }
+
+    pub(crate) fn extract_task_id(self) -> Result<String, TaskResponseError> {
+        match self {
+            TaskApiResponse { error_id: 0, task_id: Some(serde_json::Value::Number(task_id)), .. } =>
+                Ok(task_id.to_string()),
+            TaskApiResponse { error_id: 0, task_id: Some(serde_json::Value::String(task_id)), .. } =>
+                Ok(task_id),
+            other =>
+                Err(other.into_error()),
+        }
+    }
+
+    pub(crate) fn extract_poll_result(self) -> Result<TaskPollResult, TaskResponseError> {
+        match self {
+            TaskApiResponse { error_id: 0, status: Some(status), .. } if status == "processing" =>
+                Ok(TaskPollResult::NotReady),
+            TaskApiResponse { error_id: 0, status: Some(status), solution: Some(solution), .. } if status == "ready" =>
+                Ok(TaskPollResult::Ready { solution, }),
+            other =>
+                Err(other.into_error()),
+        }
+    }
+
+    pub(crate) fn extract_balance(self) -> Result<String, TaskResponseError> {
+        match self {
+            TaskApiResponse { error_id: 0, balance: Some(serde_json::Value::Number(balance)), .. } =>
+                Ok(balance.to_string()),
+            TaskApiResponse { error_id: 0, balance: Some(serde_json::Value::String(balance)), .. } =>
+                Ok(balance),
+            other =>
+                Err(other.into_error()),
+        }
+    }
+
+    pub(crate) fn extract_success(self) -> Result<(), TaskResponseError> {
+        match self {
+            TaskApiResponse { error_id: 0, .. } =>
+                Ok(()),
+            other =>
+                Err(other.into_error()),
+        }
+    }
+
+    fn into_error(self) -> TaskResponseError {
+        let error_code = match &self.error_code {
+            Some(error_code) if self.error_id != 0 =>
+                error_code.as_str(),
+            _ =>
+                return TaskResponseError::UnexpectedApiResponse(Box::new(self)),
+        };
+        match error_code {
+            "ERROR_KEY_DOES_NOT_EXIST" => TaskResponseError::KeyDoesNotExist,
+            "ERROR_NO_SLOT_AVAILABLE" => TaskResponseError::NoSlotAvailable,
+            "ERROR_ZERO_CAPTCHA_FILESIZE" => TaskResponseError::ZeroCaptchaFilesize,
+            "ERROR_TOO_BIG_CAPTCHA_FILESIZE" => TaskResponseError::TooBigCaptchaFilesize,
+            "ERROR_ZERO_BALANCE" => TaskResponseError::ZeroBalance,
+            "ERROR_IP_NOT_ALLOWED" => TaskResponseError::IpNotAllowed,
+            "ERROR_CAPTCHA_UNSOLVABLE" => TaskResponseError::CaptchaUnsolvable,
+            "ERROR_BAD_DUPLICATES" => TaskResponseError::BadDuplicates,
+            "ERROR_NO_SUCH_METHOD" => TaskResponseError::NoSuchMethod,
+            "ERROR_IMAGE_TYPE_NOT_SUPPORTED" => TaskResponseError::ImageTypeNotSupported,
+            "ERROR_NO_SUCH_CAPCHA_ID" => TaskResponseError::NoSuchCaptchaId,
+            "ERROR_IP_BLOCKED" => TaskResponseError::IpBlocked,
+            "ERROR_TASK_ABSENT" => TaskResponseError::TaskAbsent,
+            "ERROR_TASK_NOT_SUPPORTED" => TaskResponseError::TaskNotSupported,
+            "ERROR_RECAPTCHA_INVALID_SITEKEY" => TaskResponseError::RecaptchaInvalidSitekey,
+            "ERROR_ACCOUNT_SUSPENDED" => TaskResponseError::AccountSuspended,
+            "ERROR_BAD_PARAMETERS" => TaskResponseError::BadParameters,
+            "ERROR_BAD_IMGINSTRUCTIONS" => TaskResponseError::BadImginstructions,
+            "ERROR_BAD_PROXY" => TaskResponseError::BadProxy,
+            "ERROR_PROXY_CONNECTION_FAILED" => TaskResponseError::ProxyConnectionFailed,
+            "ERROR_TOKEN_EXPIRED" => TaskResponseError::TokenExpired,
+            "ERROR_WRONG_CAPTCHA_ID" => TaskResponseError::WrongCaptchaId,
+            _ => TaskResponseError::UnexpectedApiResponse(Box::new(self)),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    time::{
+        Duration,
+    },
+};
+
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    IMAGE_INITIAL_DELAY_MS,
+    IMAGE_POLL_INTERVAL_MS,
+    task,
+};
+
+pub struct Captcha {
+    question: String,
+    maybe_language: Option<String>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_question: Option<String>,
+    maybe_language: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    QuestionIsNotProvided,
+    InvalidLanguage { language: String, },
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_question<T>(mut self, question: T) -> Self where T: AsRef<str> {
+        self.maybe_question = Some(question.as_ref().to_string());
+        self
+    }
+
+    pub fn set_language<T>(mut self, language: T) -> Self where T: AsRef<str> {
+        self.maybe_language = Some(language.as_ref().to_string());
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let question = self.maybe_question
+            .filter(|question| !question.trim().is_empty())
+            .ok_or(BuilderError::QuestionIsNotProvided)?;
+        if let Some(language) = &self.maybe_language {
+            if language.len() != 2 || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(BuilderError::InvalidLanguage { language: language.clone(), });
+            }
+        }
+        Ok(Captcha {
+            question,
+            maybe_language: self.maybe_language,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    comment: String,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {
+    UnsupportedLanguagePool { lang: String, },
+}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "text";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!("building textcaptcha request with question.len = {}", self.question.len());
+
+        let mut form = vec![
+            ("method", "post".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("textcaptcha", self.question.clone()),
+        ];
+        if let Some(language) = &self.maybe_language {
+            form.push(("lang", language.clone()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    fn language_pool(&self) -> Result<Option<&'static str>, Self::PrepareRequestError> {
+        match &self.maybe_language {
+            Some(lang) =>
+                task::language_pool(lang)
+                    .map(Some)
+                    .ok_or_else(|| PrepareRequestError::UnsupportedLanguagePool { lang: lang.clone(), }),
+            None =>
+                Ok(None),
+        }
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: "TextCaptchaTask",
+            comment: self.question.clone(),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "text")
+    }
+
+    fn recommended_initial_delay() -> Duration {
+        Duration::from_millis(IMAGE_INITIAL_DELAY_MS)
+    }
+
+    fn recommended_poll_interval() -> Duration {
+        Duration::from_millis(IMAGE_POLL_INTERVAL_MS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_pool_from_language() {
+        let captcha = CaptchaBuilder::new().set_question("2 + 2?").set_language("ru").finish().unwrap();
+        assert_eq!(captcha.language_pool().unwrap(), Some("rn"));
+        let captcha = CaptchaBuilder::new().set_question("2 + 2?").finish().unwrap();
+        assert_eq!(captcha.language_pool().unwrap(), None);
+    }
+
+    #[test]
+    fn language_pool_unsupported() {
+        let captcha = CaptchaBuilder::new().set_question("2 + 2?").set_language("de").finish().unwrap();
+        assert!(matches!(captcha.language_pool(), Err(PrepareRequestError::UnsupportedLanguagePool { .. })));
+    }
+}
@@ -0,0 +1,223 @@
+use reqwest::{
+    RequestBuilder,
+};
+
+use serde_derive::{
+    Serialize,
+};
+
+use async_trait::{
+    async_trait,
+};
+
+use crate::{
+    ApiToken,
+    CaptchaRequest,
+    DecodeAnswerError,
+    proxy::{
+        Proxy,
+        TaskProxy,
+    },
+};
+
+pub struct Captcha {
+    sitekey: String,
+    pageurl: String,
+    maybe_action: Option<String>,
+    maybe_cdata: Option<String>,
+    maybe_pagedata: Option<String>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Default)]
+pub struct CaptchaBuilder {
+    maybe_sitekey: Option<String>,
+    maybe_pageurl: Option<String>,
+    maybe_action: Option<String>,
+    maybe_cdata: Option<String>,
+    maybe_pagedata: Option<String>,
+    maybe_user_agent: Option<String>,
+    maybe_proxy: Option<Proxy>,
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    SitekeyIsNotProvided,
+    PageurlIsNotProvided,
+    ChallengePageActionIsNotProvided,
+    ChallengePageCdataIsNotProvided,
+    ChallengePageUserAgentIsNotProvided,
+}
+
+impl CaptchaBuilder {
+    pub fn new() -> CaptchaBuilder {
+        CaptchaBuilder::default()
+    }
+
+    pub fn set_sitekey<T>(mut self, sitekey: T) -> Self where T: AsRef<str> {
+        self.maybe_sitekey = Some(sitekey.as_ref().to_string());
+        self
+    }
+
+    pub fn set_pageurl<T>(mut self, pageurl: T) -> Self where T: AsRef<str> {
+        self.maybe_pageurl = Some(pageurl.as_ref().to_string());
+        self
+    }
+
+    pub fn set_action<T>(mut self, action: T) -> Self where T: AsRef<str> {
+        self.maybe_action = Some(action.as_ref().to_string());
+        self
+    }
+
+    pub fn set_cdata<T>(mut self, cdata: T) -> Self where T: AsRef<str> {
+        self.maybe_cdata = Some(cdata.as_ref().to_string());
+        self
+    }
+
+    pub fn set_challenge_page<A, C, P>(mut self, action: A, cdata: C, pagedata: P) -> Self
+    where A: AsRef<str>,
+          C: AsRef<str>,
+          P: AsRef<str>,
+    {
+        self.maybe_action = Some(action.as_ref().to_string());
+        self.maybe_cdata = Some(cdata.as_ref().to_string());
+        self.maybe_pagedata = Some(pagedata.as_ref().to_string());
+        self
+    }
+
+    pub fn set_user_agent<T>(mut self, user_agent: T) -> Self where T: AsRef<str> {
+        self.maybe_user_agent = Some(user_agent.as_ref().to_string());
+        self
+    }
+
+    pub fn set_proxy(mut self, proxy: Proxy) -> Self {
+        self.maybe_proxy = Some(proxy);
+        self
+    }
+
+    pub fn finish(self) -> Result<Captcha, BuilderError> {
+        let sitekey = self.maybe_sitekey
+            .filter(|sitekey| !sitekey.is_empty())
+            .ok_or(BuilderError::SitekeyIsNotProvided)?;
+        let pageurl = self.maybe_pageurl
+            .filter(|pageurl| !pageurl.is_empty())
+            .ok_or(BuilderError::PageurlIsNotProvided)?;
+        if self.maybe_pagedata.is_some() {
+            if self.maybe_action.is_none() {
+                return Err(BuilderError::ChallengePageActionIsNotProvided);
+            }
+            if self.maybe_cdata.is_none() {
+                return Err(BuilderError::ChallengePageCdataIsNotProvided);
+            }
+            if self.maybe_user_agent.is_none() {
+                return Err(BuilderError::ChallengePageUserAgentIsNotProvided);
+            }
+        }
+        Ok(Captcha {
+            sitekey,
+            pageurl,
+            maybe_action: self.maybe_action,
+            maybe_cdata: self.maybe_cdata,
+            maybe_pagedata: self.maybe_pagedata,
+            maybe_user_agent: self.maybe_user_agent,
+            maybe_proxy: self.maybe_proxy,
+        })
+    }
+}
+
+impl Captcha {
+    pub fn is_challenge_page(&self) -> bool {
+        self.maybe_pagedata.is_some()
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    #[serde(rename = "type")]
+    task_type: &'static str,
+    #[serde(rename = "websiteURL")]
+    website_url: String,
+    website_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pagedata: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_agent: Option<String>,
+    #[serde(flatten)]
+    maybe_proxy: Option<TaskProxy>,
+}
+
+#[derive(Debug)]
+pub enum PrepareRequestError {}
+
+#[async_trait]
+impl CaptchaRequest for Captcha {
+    type PrepareRequestError = PrepareRequestError;
+    type Answer = String;
+    type Task = Task;
+    const CAPTCHA_TYPE: &'static str = "turnstile";
+
+    async fn prepare_request(&self, api_token: &ApiToken, request_builder: RequestBuilder) -> Result<RequestBuilder, Self::PrepareRequestError> {
+        log::debug!(
+            "building turnstile request for sitekey = {} on {} (challenge page = {})",
+            self.sitekey,
+            self.pageurl,
+            self.is_challenge_page(),
+        );
+
+        let mut form = vec![
+            ("method", "turnstile".to_string()),
+            ("key", api_token.key.clone()),
+            ("json", "1".to_string()),
+            ("sitekey", self.sitekey.clone()),
+            ("pageurl", self.pageurl.clone()),
+        ];
+        if let Some(action) = &self.maybe_action {
+            form.push(("action", action.clone()));
+        }
+        if let Some(cdata) = &self.maybe_cdata {
+            form.push(("data", cdata.clone()));
+        }
+        if let Some(pagedata) = &self.maybe_pagedata {
+            form.push(("pagedata", pagedata.clone()));
+        }
+        if let Some(user_agent) = &self.maybe_user_agent {
+            form.push(("userAgent", user_agent.clone()));
+        }
+        if let Some(proxy) = &self.maybe_proxy {
+            form.push(("proxy", proxy.proxy_param()));
+            form.push(("proxytype", proxy.proxy_type().as_str().to_string()));
+        }
+
+        Ok(request_builder.form(&form))
+    }
+
+    async fn prepare_task(&self) -> Result<Self::Task, Self::PrepareRequestError> {
+        Ok(Task {
+            task_type: if self.maybe_proxy.is_some() { "TurnstileTask" } else { "TurnstileTaskProxyless" },
+            website_url: self.pageurl.clone(),
+            website_key: self.sitekey.clone(),
+            action: self.maybe_action.clone(),
+            data: self.maybe_cdata.clone(),
+            pagedata: self.maybe_pagedata.clone(),
+            user_agent: self.maybe_user_agent.clone(),
+            maybe_proxy: self.maybe_proxy.as_ref().map(Proxy::task_proxy),
+        })
+    }
+
+    fn parse_answer(answer: String) -> Result<Self::Answer, DecodeAnswerError> {
+        Ok(answer)
+    }
+    fn parse_solution(solution: serde_json::Value) -> Result<Self::Answer, DecodeAnswerError> {
+        DecodeAnswerError::solution_field(&solution, "token")
+    }
+
+    fn returns_user_agent() -> bool {
+        true
+    }
+}